@@ -0,0 +1,45 @@
+// HGET 基准测试：在不同大小的哈希表上执行 HGET，单次耗时应与哈希表大小无关
+// 运行方式：cargo bench --bench hget
+use rudis::command::table::execute_command;
+use rudis::connection::client::Client;
+use rudis::db::storage::Storage;
+use std::hint::black_box;
+use std::time::Instant;
+
+const SIZES: [usize; 4] = [10, 1_000, 100_000, 1_000_000];
+const ITERATIONS: usize = 200_000;
+// 每条 HSET 命令写入的field数量
+const BATCH: usize = 1_000;
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    for size in SIZES {
+        let mut client = Client::new(Storage::new());
+        let key = format!("hash:{}", size).into_bytes();
+        for batch_start in (0..size).step_by(BATCH) {
+            let mut parts = vec![b"hset".to_vec(), key.clone()];
+            for i in batch_start..(batch_start + BATCH).min(size) {
+                parts.push(format!("field:{}", i).into_bytes());
+                parts.push(b"value".to_vec());
+            }
+            execute_command(parts, &mut client).await;
+        }
+
+        let commands: Vec<Vec<Vec<u8>>> = (0..ITERATIONS)
+            .map(|i| {
+                let field = format!("field:{}", i % size).into_bytes();
+                vec![b"hget".to_vec(), key.clone(), field]
+            })
+            .collect();
+        let start = Instant::now();
+        for parts in commands {
+            black_box(execute_command(parts, &mut client).await);
+        }
+        let elapsed = start.elapsed();
+        println!(
+            "hash size {:>9}: {:>8.0} ns/HGET",
+            size,
+            elapsed.as_nanos() as f64 / ITERATIONS as f64
+        );
+    }
+}
@@ -0,0 +1,276 @@
+use crate::connection::client::Client;
+use crate::operations::utils::parse_number;
+use crate::protocol::frame::Frame;
+use std::future::Future;
+use std::pin::Pin;
+
+pub type CommandFuture<'a> = Pin<Box<dyn Future<Output = Frame> + Send + 'a>>;
+
+// 所有命令处理函数的统一接口
+pub trait Command: Send + Sync {
+    fn execute<'a>(&self, parts: Vec<Vec<u8>>, client: &'a mut Client) -> CommandFuture<'a>;
+}
+
+// 辅助 trait，使 `async fn(Vec<Vec<u8>>, &mut Client) -> Frame` 形式的处理函数自动实现 Command
+pub trait Handler<'a>: Fn(Vec<Vec<u8>>, &'a mut Client) -> Self::Fut {
+    type Fut: Future<Output = Frame> + Send + 'a;
+}
+
+impl<'a, F, Fut> Handler<'a> for F
+where
+    F: Fn(Vec<Vec<u8>>, &'a mut Client) -> Fut,
+    Fut: Future<Output = Frame> + Send + 'a,
+{
+    type Fut = Fut;
+}
+
+impl<F> Command for F
+where
+    F: for<'a> Handler<'a> + Send + Sync,
+{
+    fn execute<'a>(&self, parts: Vec<Vec<u8>>, client: &'a mut Client) -> CommandFuture<'a> {
+        Box::pin(self(parts, client))
+    }
+}
+
+// 从命令的参数（包含命令名）中找出所有 key 的下标，用于 key 的位置随参数变化的命令
+pub type KeyFinder = fn(&[Vec<u8>]) -> Vec<usize>;
+
+// 下标 INDEX 处为 numkeys、随后紧跟 numkeys 个 key 的命令，如 LMPOP、SINTERCARD
+// numkeys 不合法或参数不够时没有 key
+pub fn numkeys_positions<const INDEX: usize>(args: &[Vec<u8>]) -> Vec<usize> {
+    let Some(numkeys) = args.get(INDEX).and_then(|arg| parse_number::<usize>(arg)) else {
+        return vec![];
+    };
+    if numkeys == 0 || numkeys >= args.len() - INDEX {
+        return vec![];
+    }
+    (INDEX + 1..=INDEX + numkeys).collect()
+}
+
+// 命令标志
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandFlag {
+    // 会修改数据
+    Write,
+    // 只读取数据
+    ReadOnly,
+    // 时间复杂度为 O(1) 或 O(log(N))
+    Fast,
+    // 可能阻塞客户端
+    Blocking,
+}
+
+impl CommandFlag {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CommandFlag::Write => "write",
+            CommandFlag::ReadOnly => "readonly",
+            CommandFlag::Fast => "fast",
+            CommandFlag::Blocking => "blocking",
+        }
+    }
+}
+
+// 命令所属的分组，与 Redis 的 group 一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandGroup {
+    Generic,
+    String,
+    List,
+    Set,
+    SortedSet,
+    Hash,
+    Bitmap,
+    Connection,
+    Server,
+}
+
+impl CommandGroup {
+    // 按 HELP 输出顺序排列的所有分组
+    pub const ALL: [CommandGroup; 9] = [
+        CommandGroup::Generic,
+        CommandGroup::String,
+        CommandGroup::List,
+        CommandGroup::Set,
+        CommandGroup::SortedSet,
+        CommandGroup::Hash,
+        CommandGroup::Bitmap,
+        CommandGroup::Connection,
+        CommandGroup::Server,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CommandGroup::Generic => "generic",
+            CommandGroup::String => "string",
+            CommandGroup::List => "list",
+            CommandGroup::Set => "set",
+            CommandGroup::SortedSet => "sorted-set",
+            CommandGroup::Hash => "hash",
+            CommandGroup::Bitmap => "bitmap",
+            CommandGroup::Connection => "connection",
+            CommandGroup::Server => "server",
+        }
+    }
+}
+
+// 子命令的说明，如 COMMAND COUNT
+pub struct SubcommandDoc {
+    pub name: &'static str,
+    // 参数个数（包含命令名和子命令名），含义与 CommandSpec::arity 相同
+    pub arity: i32,
+    pub syntax: &'static str,
+    pub summary: &'static str,
+}
+
+// 命令表中的一条命令
+pub struct CommandSpec {
+    // 小写的命令名
+    pub name: &'static str,
+    // 参数个数（包含命令名），正数表示必须相等，负数表示至少为其绝对值
+    pub arity: i32,
+    pub flags: &'static [CommandFlag],
+    // 第一个 key 的位置，0 表示命令不带 key
+    pub first_key: i32,
+    // 最后一个 key 的位置，-1 表示直到最后一个参数
+    pub last_key: i32,
+    // 相邻两个 key 之间的间隔
+    pub step: i32,
+    // key 的位置取决于其他参数时（movablekeys）用它找出 key，此时 first_key 为 0
+    pub key_finder: Option<KeyFinder>,
+    pub group: CommandGroup,
+    // 命令首次出现的 Redis 版本
+    pub since: &'static str,
+    // 时间复杂度
+    pub complexity: &'static str,
+    // 一句话说明
+    pub summary: &'static str,
+    // 命令名之后的参数格式，如 "key value"
+    pub syntax: &'static str,
+    pub subcommands: Vec<SubcommandDoc>,
+    pub handler: Box<dyn Command>,
+}
+
+impl CommandSpec {
+    pub fn new(
+        name: &'static str,
+        arity: i32,
+        flags: &'static [CommandFlag],
+        handler: impl Command + 'static,
+    ) -> Self {
+        CommandSpec {
+            name,
+            arity,
+            flags,
+            first_key: 0,
+            last_key: 0,
+            step: 0,
+            key_finder: None,
+            group: CommandGroup::Generic,
+            since: "",
+            complexity: "",
+            summary: "",
+            syntax: "",
+            subcommands: Vec::new(),
+            handler: Box::new(handler),
+        }
+    }
+
+    // 设置 key 的位置
+    pub fn keys(mut self, first_key: i32, last_key: i32, step: i32) -> Self {
+        self.first_key = first_key;
+        self.last_key = last_key;
+        self.step = step;
+        self
+    }
+
+    // 设置查找 key 的函数，用于 key 的位置取决于其他参数的命令
+    pub fn movable_keys(mut self, finder: KeyFinder) -> Self {
+        self.key_finder = Some(finder);
+        self
+    }
+
+    // 设置文档信息，供 COMMAND DOCS 使用
+    pub fn doc(
+        mut self,
+        group: CommandGroup,
+        since: &'static str,
+        complexity: &'static str,
+        summary: &'static str,
+    ) -> Self {
+        self.group = group;
+        self.since = since;
+        self.complexity = complexity;
+        self.summary = summary;
+        self
+    }
+
+    // 设置参数格式，供 HELP 使用
+    pub fn syntax(mut self, syntax: &'static str) -> Self {
+        self.syntax = syntax;
+        self
+    }
+
+    // 添加一个子命令的说明，带子命令的命令会自动支持 `<命令> HELP`
+    pub fn subcommand(
+        mut self,
+        name: &'static str,
+        arity: i32,
+        syntax: &'static str,
+        summary: &'static str,
+    ) -> Self {
+        self.subcommands.push(SubcommandDoc {
+            name,
+            arity,
+            syntax,
+            summary,
+        });
+        self
+    }
+
+    // 完整的用法，如 "SET key value"
+    pub fn usage(&self) -> String {
+        let name = self.name.to_uppercase();
+        if self.syntax.is_empty() {
+            name
+        } else {
+            format!("{} {}", name, self.syntax)
+        }
+    }
+
+    // 检查参数个数是否符合 arity
+    pub fn check_arity(&self, argc: usize) -> bool {
+        let argc = argc as i32;
+        if self.arity >= 0 {
+            argc == self.arity
+        } else {
+            argc >= -self.arity
+        }
+    }
+
+    pub fn has_flag(&self, flag: CommandFlag) -> bool {
+        self.flags.contains(&flag)
+    }
+
+    // 根据 key 的位置信息从参数（包含命令名）中取出所有 key 的下标
+    pub fn key_positions(&self, args: &[Vec<u8>]) -> Vec<usize> {
+        if let Some(finder) = self.key_finder {
+            return finder(args);
+        }
+        if self.first_key <= 0 {
+            return vec![];
+        }
+        let argc = args.len() as i32;
+        let last = if self.last_key < 0 {
+            argc + self.last_key
+        } else {
+            self.last_key.min(argc - 1)
+        };
+        let step = self.step.max(1) as usize;
+        (self.first_key..=last)
+            .step_by(step)
+            .map(|i| i as usize)
+            .collect()
+    }
+}
@@ -0,0 +1,83 @@
+use crate::command::spec::CommandSpec;
+use crate::connection::client::Client;
+use crate::operations;
+use crate::operations::utils::wrong_args;
+use crate::protocol::frame::Frame;
+use log::error;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+// 全局命令表，首次使用时由各类型模块注册自己的命令
+pub static COMMAND_TABLE: LazyLock<CommandTable> = LazyLock::new(|| {
+    let mut table = CommandTable::new();
+    operations::help::help::register(&mut table);
+    operations::connection::ops::register(&mut table);
+    operations::server::ops::register(&mut table);
+    operations::generic::ops::register(&mut table);
+    operations::expire::ops::register(&mut table);
+    operations::string::ops::register(&mut table);
+    operations::hash::ops::register(&mut table);
+    operations::hash::expire::register(&mut table);
+    operations::list::ops::register(&mut table);
+    operations::list::blocking::register(&mut table);
+    operations::set::ops::register(&mut table);
+    operations::sorted_set::ops::register(&mut table);
+    operations::bitmap::ops::register(&mut table);
+    table
+});
+
+// 命令名到命令的映射
+#[derive(Default)]
+pub struct CommandTable {
+    commands: HashMap<&'static str, CommandSpec>,
+}
+
+impl CommandTable {
+    pub fn new() -> Self {
+        CommandTable {
+            commands: HashMap::new(),
+        }
+    }
+
+    pub fn register(&mut self, spec: CommandSpec) {
+        self.commands.insert(spec.name, spec);
+    }
+
+    // 按命令名查找，不区分大小写
+    pub fn lookup(&self, name: &[u8]) -> Option<&CommandSpec> {
+        let name = String::from_utf8_lossy(name).to_lowercase();
+        self.commands.get(name.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &CommandSpec> {
+        self.commands.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+}
+
+// 查找命令并检查参数个数后执行
+pub async fn execute_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let Some(spec) = COMMAND_TABLE.lookup(&parts[0]) else {
+        error!("未定义的指令类型");
+        return Frame::error(format!(
+            "ERR unknown command '{}'",
+            String::from_utf8_lossy(&parts[0])
+        ));
+    };
+    if !spec.check_arity(parts.len()) {
+        error!("命令格式不符合！");
+        return wrong_args(spec.name);
+    }
+    // 带子命令的命令统一支持 `<命令> HELP`
+    if !spec.subcommands.is_empty() && parts.len() == 2 && parts[1].eq_ignore_ascii_case(b"help") {
+        return operations::help::help::subcommand_help(spec);
+    }
+    spec.handler.execute(parts, client).await
+}
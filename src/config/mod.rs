@@ -1,2 +1,3 @@
+#[allow(clippy::module_inception)]
 mod config;
 pub mod reader;
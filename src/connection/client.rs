@@ -0,0 +1,27 @@
+use crate::db::storage::Storage;
+use crate::protocol::encoder::Protocol;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// 全局递增的客户端 id
+static NEXT_CLIENT_ID: AtomicU64 = AtomicU64::new(1);
+
+// 单个客户端连接的状态
+pub struct Client {
+    pub id: u64,
+    // 通过 HELLO 协商的协议版本
+    pub protocol: Protocol,
+    // 通过 HELLO SETNAME 设置的连接名
+    pub name: Option<Vec<u8>>,
+    pub storage: Storage,
+}
+
+impl Client {
+    pub fn new(storage: Storage) -> Self {
+        Client {
+            id: NEXT_CLIENT_ID.fetch_add(1, Ordering::Relaxed),
+            protocol: Protocol::default(),
+            name: None,
+            storage,
+        }
+    }
+}
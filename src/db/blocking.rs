@@ -0,0 +1,95 @@
+use crate::db::storage::Db;
+use crate::protocol::frame::Frame;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+// 尝试用 key 中的元素为阻塞的客户端服务，成功时返回给客户端的回复
+// 在持有键空间锁时调用，元素的弹出和回复的生成是原子的
+pub type ServeFn = Box<dyn Fn(&mut Db, &[u8]) -> Option<Frame> + Send + Sync>;
+
+// 尝试为阻塞的客户端服务的结果
+#[derive(Debug, PartialEq)]
+pub enum ServeResult {
+    // 已经弹出元素并生成回复
+    Served,
+    // 客户端已超时、断开或已经在其他 key 上得到服务，不再等待
+    Cancelled,
+    // key 中没有可用的元素
+    NoData,
+}
+
+#[derive(Default)]
+struct Slot {
+    // 服务成功后的回复
+    reply: Option<Frame>,
+    // 客户端已超时或断开，不再接受服务
+    cancelled: bool,
+}
+
+// 阻塞在一个或多个 key 上的客户端
+// 其他客户端写入元素后，在同一次持锁中调用 serve 弹出元素并把回复放入 slot，
+// 客户端超时或断开时把 slot 标记为取消，两者由 slot 的锁保证互斥，元素不会丢失
+pub struct Waiter {
+    slot: Mutex<Slot>,
+    notify: Notify,
+    serve: ServeFn,
+}
+
+impl Waiter {
+    pub fn new(serve: ServeFn) -> Arc<Waiter> {
+        Arc::new(Waiter {
+            slot: Mutex::new(Slot::default()),
+            notify: Notify::new(),
+            serve,
+        })
+    }
+
+    // 是否仍在等待服务
+    pub fn is_waiting(&self) -> bool {
+        let slot = self.slot.lock().unwrap();
+        !slot.cancelled && slot.reply.is_none()
+    }
+
+    // 用 key 中的元素为客户端服务
+    pub fn try_serve(&self, db: &mut Db, key: &[u8]) -> ServeResult {
+        let mut slot = self.slot.lock().unwrap();
+        if slot.cancelled || slot.reply.is_some() {
+            return ServeResult::Cancelled;
+        }
+        let Some(reply) = (self.serve)(db, key) else {
+            return ServeResult::NoData;
+        };
+        slot.reply = Some(reply);
+        self.notify.notify_one();
+        ServeResult::Served
+    }
+
+    // 等待服务，timeout 为 None 时一直等待，超时返回 None
+    // 返回的 future 被丢弃（客户端断开）时同样会取消等待
+    pub async fn wait(&self, timeout: Option<Duration>) -> Option<Frame> {
+        let _guard = CancelOnDrop(self);
+        match timeout {
+            Some(timeout) => {
+                let _ = tokio::time::timeout(timeout, self.notify.notified()).await;
+            }
+            None => self.notify.notified().await,
+        }
+        self.cancel()
+    }
+
+    // 取消等待，返回取消前已经得到的回复
+    fn cancel(&self) -> Option<Frame> {
+        let mut slot = self.slot.lock().unwrap();
+        slot.cancelled = true;
+        slot.reply.take()
+    }
+}
+
+struct CancelOnDrop<'a>(&'a Waiter);
+
+impl Drop for CancelOnDrop<'_> {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
@@ -0,0 +1,54 @@
+use std::ops::Range;
+
+// SCAN、HSCAN、SSCAN、ZSCAN 的游标，元素保存在可以按下标访问的表（IndexMap、IndexSet）中，
+// 游标就是还没有遍历的下标个数，每次从后往前取出 count 个下标，不需要额外的索引
+// 删除元素时表尾的元素被移动到删除的位置，只会把已经遍历过的元素移到前面（可能重复返回），
+// 新增的元素总是追加在表尾（可能不返回），因此迭代期间一直存在的元素至少会被返回一次
+// 返回本次遍历的下标范围和下一次的游标，0 表示迭代结束
+pub fn scan_range(len: usize, cursor: u64, count: usize) -> (Range<usize>, u64) {
+    let end = match cursor {
+        0 => len,
+        cursor => len.min(usize::try_from(cursor).unwrap_or(usize::MAX)),
+    };
+    let start = end.saturating_sub(count);
+    (start..end, start as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indexmap::IndexSet;
+
+    #[test]
+    fn scan_returns_every_remaining_key_while_deleting() {
+        let mut keys: IndexSet<String> = (0..1000).map(|i| format!("key:{}", i)).collect();
+        let mut seen = Vec::new();
+        let mut cursor = 0;
+        loop {
+            let (range, next) = scan_range(keys.len(), cursor, 10);
+            assert!(range.len() == 10 || next == 0);
+            seen.extend(range.filter_map(|index| keys.get_index(index).cloned()));
+            // 每一轮删除一个已经返回的 key 和一个还没有返回的 key，再追加一个新 key
+            if let Some(first) = keys.first().cloned() {
+                keys.swap_remove(&first);
+            }
+            keys.swap_remove(&seen[seen.len() / 2]);
+            keys.insert(format!("new:{}", seen.len()));
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        for key in keys.iter().filter(|key| key.starts_with("key:")) {
+            assert!(seen.contains(key), "{} was never returned", key);
+        }
+    }
+
+    #[test]
+    fn scan_of_empty_table_ends_immediately() {
+        assert_eq!(scan_range(0, 0, 10), (0..0, 0));
+        assert_eq!(scan_range(0, 12345, 10), (0..0, 0));
+        assert_eq!(scan_range(5, 0, 10), (0..5, 0));
+        assert_eq!(scan_range(25, 0, 10), (15..25, 15));
+    }
+}
@@ -0,0 +1,31 @@
+use crate::db::storage::{Db, Storage};
+use std::time::{Duration, Instant};
+
+// 每秒执行主动过期的次数，与 Redis 的 hz 默认值一致
+const ACTIVE_EXPIRE_HZ: u64 = 10;
+// 每轮抽样的 key 数量
+const ACTIVE_EXPIRE_SAMPLE: usize = 20;
+// 一次主动过期最多占用键空间锁的时间
+const ACTIVE_EXPIRE_TIME_LIMIT: Duration = Duration::from_millis(25);
+
+// 后台主动过期任务，只被读取过的过期 key 和哈希表 field 不会被惰性删除，由这里负责回收
+pub async fn active_expire(storage: Storage) {
+    let mut interval = tokio::time::interval(Duration::from_millis(1000 / ACTIVE_EXPIRE_HZ));
+    loop {
+        interval.tick().await;
+        let mut db = storage.db.lock().await;
+        let start = Instant::now();
+        expire_cycle(&mut db, start, Db::sample_expired);
+        expire_cycle(&mut db, start, Db::sample_expired_fields);
+    }
+}
+
+// 反复调用 sample 抽样回收，过期比例超过 25% 时说明过期的仍然很多，继续下一轮
+fn expire_cycle(db: &mut Db, start: Instant, sample: fn(&mut Db, usize) -> (usize, usize)) {
+    loop {
+        let (sampled, expired) = sample(db, ACTIVE_EXPIRE_SAMPLE);
+        if sampled == 0 || expired * 4 <= sampled || start.elapsed() > ACTIVE_EXPIRE_TIME_LIMIT {
+            break;
+        }
+    }
+}
@@ -0,0 +1,5 @@
+pub mod blocking;
+pub mod cursor;
+pub mod expire;
+pub mod storage;
+pub mod value;
@@ -0,0 +1,467 @@
+use crate::db::blocking::{ServeResult, Waiter};
+use crate::db::cursor::scan_range;
+use crate::db::value::{Value, ValueType};
+use crate::operations::utils::wrong_type;
+use crate::protocol::frame::Frame;
+use indexmap::{IndexMap, IndexSet};
+use rand::Rng;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+
+// 所有 key 共用的键空间，clone 时只复制内部的 Arc
+#[derive(Clone)]
+pub struct Storage {
+    pub db: Arc<Mutex<Db>>,
+}
+
+impl Storage {
+    pub fn new() -> Self {
+        Storage {
+            db: Arc::new(Mutex::new(Db::new())),
+        }
+    }
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 当前的 Unix 时间戳，单位毫秒
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+// key 到值的映射，按类型读取时类型不符会返回 WRONGTYPE 错误
+// 已过期的 key 对所有读操作都不可见，写操作访问到时会被真正删除，
+// 其余的由后台的主动过期任务定期抽样回收
+#[derive(Default)]
+pub struct Db {
+    // 可以按下标访问，RANDOMKEY 和主动过期的抽样都是 O(1)，SCAN 的游标也是下标
+    entries: IndexMap<Vec<u8>, Value>,
+    // 设置了过期时间的 key 到过期时刻（Unix 毫秒时间戳）的映射
+    expires: IndexMap<Vec<u8>, u64>,
+    // 有 field 设置了过期时间的哈希表，供主动过期任务抽样，
+    // 其中已被删除或不再有 field 过期时间的 key 在抽样时移除
+    field_expires: IndexSet<Vec<u8>>,
+    // 阻塞在 key 上等待元素的客户端，按阻塞的先后排队
+    blocked: HashMap<Vec<u8>, VecDeque<Arc<Waiter>>>,
+    // 写入了新元素、需要为阻塞客户端服务的 key
+    ready: VecDeque<Vec<u8>>,
+    // 正在为阻塞客户端服务，服务过程中写入的 key 只加入 ready，由外层继续处理
+    serving: bool,
+}
+
+impl Db {
+    pub fn new() -> Self {
+        Db::default()
+    }
+
+    // key 本身已过期，或者是所有 field 都已过期的哈希表
+    fn is_expired(&self, key: &[u8], now: u64) -> bool {
+        self.expires.get(key).is_some_and(|at| *at <= now)
+            || matches!(self.entries.get(key), Some(Value::Hash(hash)) if hash.all_expired(now))
+    }
+
+    // key 已过期时将其删除，返回是否删除
+    fn expire_if_needed(&mut self, key: &[u8]) -> bool {
+        if !self.is_expired(key, now_millis()) {
+            return false;
+        }
+        self.expires.swap_remove(key);
+        self.remove_entry(key);
+        true
+    }
+
+    // 从键空间中删除 key，表尾的 key 移动到删除的位置，SCAN 依赖这一点，不能改变其余 key 的顺序
+    fn remove_entry(&mut self, key: &[u8]) -> Option<Value> {
+        self.entries.swap_remove(key)
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<&Value> {
+        if self.is_expired(key, now_millis()) {
+            return None;
+        }
+        self.entries.get(key)
+    }
+
+    // 读取指定类型的值，key 不存在时返回 Ok(None)
+    pub fn get_as<T: ValueType>(&self, key: &[u8]) -> Result<Option<&T>, Frame> {
+        match self.get(key) {
+            Some(value) => T::from_value(value).map(Some).ok_or_else(wrong_type),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_mut_as<T: ValueType>(&mut self, key: &[u8]) -> Result<Option<&mut T>, Frame> {
+        self.expire_if_needed(key);
+        match self.entries.get_mut(key) {
+            Some(value) => T::from_value_mut(value).map(Some).ok_or_else(wrong_type),
+            None => Ok(None),
+        }
+    }
+
+    // 读取指定类型的值，key 不存在时用 init 创建
+    pub fn get_or_insert_with<T: ValueType>(
+        &mut self,
+        key: &[u8],
+        init: impl FnOnce() -> T,
+    ) -> Result<&mut T, Frame> {
+        self.expire_if_needed(key);
+        let value = self
+            .entries
+            .entry(key.to_vec())
+            .or_insert_with(|| init().into_value());
+        T::from_value_mut(value).ok_or_else(wrong_type)
+    }
+
+    pub fn get_or_default<T: ValueType + Default>(&mut self, key: &[u8]) -> Result<&mut T, Frame> {
+        self.get_or_insert_with(key, T::default)
+    }
+
+    // 写入一个值，覆盖原来任意类型的值，同时清除原来的过期时间
+    pub fn insert(&mut self, key: Vec<u8>, value: Value) -> Option<Value> {
+        self.expire_if_needed(&key);
+        self.expires.swap_remove(&key);
+        if let Value::Hash(hash) = &value
+            && hash.has_expires()
+        {
+            self.field_expires.insert(key.clone());
+        }
+        // RENAME、COPY 等命令写入的列表同样可以唤醒阻塞的客户端
+        let is_list = matches!(value, Value::List(_));
+        let old = self.entries.insert(key.clone(), value);
+        if is_list {
+            self.wake_blocked(&key);
+        }
+        old
+    }
+
+    pub fn remove(&mut self, key: &[u8]) -> Option<Value> {
+        if self.expire_if_needed(key) {
+            return None;
+        }
+        self.expires.swap_remove(key);
+        self.remove_entry(key)
+    }
+
+    // 将 key 重命名为 new_key，覆盖 new_key 原来的值，过期时间随 key 一起转移
+    // key 不存在时返回 false
+    pub fn rename(&mut self, key: &[u8], new_key: Vec<u8>) -> bool {
+        let Some((value, expire)) = self.remove_with_expire(key) else {
+            return false;
+        };
+        self.insert(new_key.clone(), value);
+        if let Some(at) = expire {
+            self.expires.insert(new_key, at);
+        }
+        true
+    }
+
+    // 删除 key 并返回它的值和过期时间
+    fn remove_with_expire(&mut self, key: &[u8]) -> Option<(Value, Option<u64>)> {
+        if self.expire_if_needed(key) {
+            return None;
+        }
+        let value = self.remove_entry(key)?;
+        Some((value, self.expires.swap_remove(key)))
+    }
+
+    // 随机返回一个 key，随机到的已过期 key 会被删除，键空间为空时返回 None
+    pub fn random_key(&mut self) -> Option<Vec<u8>> {
+        while !self.entries.is_empty() {
+            let index = rand::thread_rng().gen_range(0..self.entries.len());
+            let (key, _) = self.entries.get_index(index)?;
+            let key = key.clone();
+            if !self.expire_if_needed(&key) {
+                return Some(key);
+            }
+        }
+        None
+    }
+
+    // 遍历所有未过期的 key
+    pub fn iter(&self) -> impl Iterator<Item = (&Vec<u8>, &Value)> {
+        let now = now_millis();
+        self.entries
+            .iter()
+            .filter(move |(key, _)| !self.is_expired(key, now))
+    }
+
+    // 从 cursor 开始取出 count 个 key，返回下一次的游标以及其中未过期的 key 和值
+    pub fn scan(&self, cursor: u64, count: usize) -> (u64, Vec<(&Vec<u8>, &Value)>) {
+        let (range, next) = scan_range(self.entries.len(), cursor, count);
+        let now = now_millis();
+        let entries = range
+            .filter_map(|index| self.entries.get_index(index))
+            .filter(|(key, _)| !self.is_expired(key, now))
+            .collect();
+        (next, entries)
+    }
+
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.get(key).is_some()
+    }
+
+    // key 的过期时刻，key 不存在或没有设置过期时间时返回 None
+    pub fn expire_time(&self, key: &[u8]) -> Option<u64> {
+        if self.is_expired(key, now_millis()) {
+            return None;
+        }
+        self.expires.get(key).copied()
+    }
+
+    // 设置过期时刻，key 不存在时返回 false
+    pub fn set_expire(&mut self, key: &[u8], at: u64) -> bool {
+        if self.expire_if_needed(key) || !self.entries.contains_key(key) {
+            return false;
+        }
+        self.expires.insert(key.to_vec(), at);
+        true
+    }
+
+    // 清除过期时间，返回 key 原来是否设置了过期时间
+    pub fn persist(&mut self, key: &[u8]) -> bool {
+        if self.expire_if_needed(key) {
+            return false;
+        }
+        self.expires.swap_remove(key).is_some()
+    }
+
+    // 从设置了过期时间的 key 中随机抽取 count 次，删除其中已过期的
+    // 返回 (抽样数, 删除数)
+    pub fn sample_expired(&mut self, count: usize) -> (usize, usize) {
+        let now = now_millis();
+        let mut rng = rand::thread_rng();
+        let sampled = count.min(self.expires.len());
+        let mut expired = 0;
+        for _ in 0..sampled {
+            if self.expires.is_empty() {
+                break;
+            }
+            let index = rng.gen_range(0..self.expires.len());
+            let Some((key, at)) = self.expires.get_index(index) else {
+                break;
+            };
+            if *at > now {
+                continue;
+            }
+            let key = key.clone();
+            self.expires.swap_remove_index(index);
+            self.remove_entry(&key);
+            expired += 1;
+        }
+        (sampled, expired)
+    }
+
+    // 哈希表的 field 设置过期时间后调用，交由主动过期任务回收
+    pub fn track_field_expires(&mut self, key: &[u8]) {
+        self.field_expires.insert(key.to_vec());
+    }
+
+    // 从有 field 设置了过期时间的哈希表中随机抽取 count 次，删除其中已过期的 field，
+    // field 全部过期的哈希表随之删除，返回 (抽样数, 存在过期 field 的哈希表数)
+    pub fn sample_expired_fields(&mut self, count: usize) -> (usize, usize) {
+        let mut rng = rand::thread_rng();
+        let sampled = count.min(self.field_expires.len());
+        let mut expired = 0;
+        for _ in 0..sampled {
+            if self.field_expires.is_empty() {
+                break;
+            }
+            let index = rng.gen_range(0..self.field_expires.len());
+            let Some(key) = self.field_expires.get_index(index).cloned() else {
+                break;
+            };
+            let Some(Value::Hash(hash)) = self.entries.get_mut(&key) else {
+                self.field_expires.swap_remove_index(index);
+                continue;
+            };
+            if hash.remove_expired() > 0 {
+                expired += 1;
+            }
+            if !hash.has_expires() {
+                self.field_expires.swap_remove_index(index);
+            }
+            if hash.is_empty() {
+                self.expires.swap_remove(&key);
+                self.remove_entry(&key);
+            }
+        }
+        (sampled, expired)
+    }
+
+    // 让 waiter 阻塞在 keys 上，顺带清理这些 key 上已经结束等待的客户端
+    pub fn block(&mut self, keys: &[Vec<u8>], waiter: &Arc<Waiter>) {
+        for key in keys {
+            let queue = self.blocked.entry(key.clone()).or_default();
+            queue.retain(|waiter| waiter.is_waiting());
+            queue.push_back(waiter.clone());
+        }
+    }
+
+    // 客户端结束等待（服务成功、超时或断开）后调用，把 waiter 从 keys 的等待队列中移除，
+    // 队列为空时一并删除
+    pub fn unblock(&mut self, keys: &[Vec<u8>], waiter: &Arc<Waiter>) {
+        for key in keys {
+            let Some(queue) = self.blocked.get_mut(key) else {
+                continue;
+            };
+            queue.retain(|other| !Arc::ptr_eq(other, waiter));
+            if queue.is_empty() {
+                self.blocked.remove(key);
+            }
+        }
+    }
+
+    // key 中写入了新元素后调用，按阻塞的先后顺序为等待该 key 的客户端服务，
+    // 直到没有元素或没有等待的客户端
+    pub fn wake_blocked(&mut self, key: &[u8]) {
+        if !self.blocked.contains_key(key) || self.ready.iter().any(|ready| ready == key) {
+            return;
+        }
+        self.ready.push_back(key.to_vec());
+        // 服务时移动到其他列表的元素会再次调用这里，只需要加入 ready
+        if self.serving {
+            return;
+        }
+        self.serving = true;
+        while let Some(key) = self.ready.pop_front() {
+            while let Some(waiter) = self
+                .blocked
+                .get(&key)
+                .and_then(|queue| queue.front())
+                .cloned()
+            {
+                // 已经不再等待的客户端直接出队，继续为后面的客户端服务
+                if waiter.try_serve(self, &key) == ServeResult::NoData {
+                    break;
+                }
+                if let Some(queue) = self.blocked.get_mut(&key) {
+                    queue.pop_front();
+                }
+            }
+            if self.blocked.get(&key).is_some_and(|queue| queue.is_empty()) {
+                self.blocked.remove(&key);
+            }
+        }
+        self.serving = false;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::blocking::ServeFn;
+    use crate::db::value::{HashValue, ListValue};
+    use std::time::Duration;
+
+    // 删除整个 key 作为服务，key 不存在时没有可用的元素
+    fn take_key() -> ServeFn {
+        Box::new(|db, key| db.remove(key).map(|_| Frame::ok()))
+    }
+
+    #[test]
+    fn unblock_removes_waiter_and_empty_queues() {
+        let mut db = Db::new();
+        let keys = vec![b"a".to_vec(), b"b".to_vec()];
+        let first = Waiter::new(Box::new(|_, _| None));
+        let second = Waiter::new(Box::new(|_, _| None));
+        db.block(&keys, &first);
+        db.block(&keys[..1], &second);
+
+        db.unblock(&keys, &first);
+        assert_eq!(db.blocked.len(), 1);
+        assert!(Arc::ptr_eq(&db.blocked[&keys[0]][0], &second));
+
+        db.unblock(&keys[..1], &second);
+        assert!(db.blocked.is_empty());
+    }
+
+    #[test]
+    fn sample_expired_reclaims_keys_without_access() {
+        let mut db = Db::new();
+        for i in 0..100 {
+            let key = format!("key:{}", i).into_bytes();
+            db.insert(key.clone(), Value::String(b"v".to_vec()));
+            // 一半的 key 已经过期，另一半永远不会过期
+            db.set_expire(&key, if i % 2 == 0 { 1 } else { u64::MAX });
+        }
+        db.insert(b"persistent".to_vec(), Value::String(b"v".to_vec()));
+
+        for _ in 0..1000 {
+            if db.entries.len() == 51 {
+                break;
+            }
+            db.sample_expired(20);
+        }
+        assert_eq!(db.entries.len(), 51);
+        assert_eq!(db.expires.len(), 50);
+        assert_eq!(db.sample_expired(20), (20, 0));
+        for _ in 0..10 {
+            let key = db.random_key().unwrap();
+            assert!(db.contains_key(&key));
+        }
+    }
+
+    #[test]
+    fn sample_expired_fields_reclaims_fields_without_access() {
+        let mut db = Db::new();
+        let mut hash = HashValue::new();
+        hash.insert(b"a".to_vec(), b"1".to_vec());
+        hash.insert(b"b".to_vec(), b"2".to_vec());
+        hash.set_expire(b"a", 1);
+        db.insert(b"h".to_vec(), Value::Hash(hash));
+        let mut expired = HashValue::new();
+        expired.insert(b"a".to_vec(), b"1".to_vec());
+        expired.set_expire(b"a", 1);
+        db.insert(b"gone".to_vec(), Value::Hash(expired));
+
+        for _ in 0..100 {
+            if db.field_expires.is_empty() {
+                break;
+            }
+            db.sample_expired_fields(20);
+        }
+        assert!(db.field_expires.is_empty());
+        assert!(!db.entries.contains_key(b"gone".as_slice()));
+        let Some(Value::Hash(hash)) = db.entries.get(b"h".as_slice()) else {
+            panic!("hash h should still exist");
+        };
+        assert_eq!(hash.len(), 1);
+        assert!(!hash.has_expires());
+    }
+
+    #[tokio::test]
+    async fn wake_skips_cancelled_waiters() {
+        let mut db = Db::new();
+        let key = b"q".to_vec();
+        let cancelled = Waiter::new(take_key());
+        let waiting = Waiter::new(take_key());
+        db.block(std::slice::from_ref(&key), &cancelled);
+        db.block(std::slice::from_ref(&key), &waiting);
+        // 超时后还没来得及从队列中移除
+        assert_eq!(cancelled.wait(Some(Duration::ZERO)).await, None);
+
+        db.insert(
+            key.clone(),
+            Value::List(ListValue::from(vec![b"a".to_vec()])),
+        );
+        assert!(!db.contains_key(&key));
+        assert!(db.blocked.is_empty());
+        assert_eq!(waiting.wait(None).await, Some(Frame::ok()));
+    }
+}
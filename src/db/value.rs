@@ -0,0 +1,435 @@
+use crate::db::cursor::scan_range;
+use crate::db::storage::now_millis;
+use indexmap::{IndexMap, IndexSet};
+use skiplist::OrderedSkipList;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+pub type ListValue = VecDeque<Vec<u8>>;
+
+// 键空间中一个 key 对应的值，每个 key 只能属于一种类型
+#[derive(Clone)]
+pub enum Value {
+    String(Vec<u8>),
+    Hash(HashValue),
+    List(ListValue),
+    Set(SetValue),
+    ZSet(SortedSet),
+}
+
+impl Value {
+    // TYPE 命令返回的类型名，位图直接存储为字符串
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::Hash(_) => "hash",
+            Value::List(_) => "list",
+            Value::Set(_) => "set",
+            Value::ZSet(_) => "zset",
+        }
+    }
+}
+
+// 哈希表，每个 field 可以单独设置过期时间
+// 已过期的 field 对所有读操作都不可见，写操作访问到时会被真正删除，
+// 其余的由后台的主动过期任务定期回收
+#[derive(Clone, Default)]
+pub struct HashValue {
+    // 可以按下标访问，HSCAN 的游标就是下标
+    fields: IndexMap<Vec<u8>, Vec<u8>>,
+    // 设置了过期时间的 field 到过期时刻（Unix 毫秒时间戳）的映射
+    expires: HashMap<Vec<u8>, u64>,
+    // 按过期时刻排序的 field，判断是否全部过期、统计和回收已过期的 field 时不需要遍历所有 field
+    expire_order: BTreeSet<(u64, Vec<u8>)>,
+}
+
+impl HashValue {
+    pub fn new() -> Self {
+        HashValue::default()
+    }
+
+    fn is_expired(&self, field: &[u8], now: u64) -> bool {
+        self.expires.get(field).is_some_and(|at| *at <= now)
+    }
+
+    // field 已过期时将其删除
+    fn expire_if_needed(&mut self, field: &[u8]) {
+        if self.is_expired(field, now_millis()) {
+            self.clear_expire(field);
+            self.fields.swap_remove(field);
+        }
+    }
+
+    // 清除 field 的过期时间，同时更新过期时刻索引，返回原来的过期时刻
+    fn clear_expire(&mut self, field: &[u8]) -> Option<u64> {
+        let at = self.expires.remove(field)?;
+        self.expire_order.remove(&(at, field.to_vec()));
+        Some(at)
+    }
+
+    // 已过期但还没有被删除的 field 数量
+    fn expired_count(&self, now: u64) -> usize {
+        self.expire_order.range(..(now + 1, Vec::new())).count()
+    }
+
+    pub fn get(&self, field: &[u8]) -> Option<&Vec<u8>> {
+        if self.is_expired(field, now_millis()) {
+            return None;
+        }
+        self.fields.get(field)
+    }
+
+    pub fn contains_key(&self, field: &[u8]) -> bool {
+        self.get(field).is_some()
+    }
+
+    // 写入 field 的值，同时清除原来的过期时间，返回原来的值
+    pub fn insert(&mut self, field: Vec<u8>, value: Vec<u8>) -> Option<Vec<u8>> {
+        self.expire_if_needed(&field);
+        self.clear_expire(&field);
+        self.fields.insert(field, value)
+    }
+
+    // 写入 field 的值，保留原来的过期时间，用于 HINCRBY 这类修改原值的命令
+    pub fn replace(&mut self, field: Vec<u8>, value: Vec<u8>) -> Option<Vec<u8>> {
+        self.expire_if_needed(&field);
+        self.fields.insert(field, value)
+    }
+
+    pub fn remove(&mut self, field: &[u8]) -> Option<Vec<u8>> {
+        self.expire_if_needed(field);
+        self.clear_expire(field);
+        self.fields.swap_remove(field)
+    }
+
+    // 遍历所有未过期的 field
+    pub fn iter(&self) -> impl Iterator<Item = (&Vec<u8>, &Vec<u8>)> {
+        let now = now_millis();
+        self.fields
+            .iter()
+            .filter(move |(field, _)| !self.is_expired(field, now))
+    }
+
+    // 从 cursor 开始取出 count 个 field，返回下一次的游标以及其中未过期的 field
+    pub fn scan(&self, cursor: u64, count: usize) -> (u64, Vec<&Vec<u8>>) {
+        let (range, next) = scan_range(self.fields.len(), cursor, count);
+        let now = now_millis();
+        let fields = range
+            .filter_map(|index| self.fields.get_index(index))
+            .map(|(field, _)| field)
+            .filter(|field| !self.is_expired(field, now))
+            .collect();
+        (next, fields)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Vec<u8>> {
+        self.iter().map(|(field, _)| field)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Vec<u8>> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    // 只需要扣除已过期但还没有被回收的 field
+    pub fn len(&self) -> usize {
+        self.fields.len() - self.expired_count(now_millis())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty() || self.all_expired(now_millis())
+    }
+
+    // field 的过期时刻，field 不存在或没有设置过期时间时返回 None
+    pub fn expire_time(&self, field: &[u8]) -> Option<u64> {
+        if self.is_expired(field, now_millis()) {
+            return None;
+        }
+        self.expires.get(field).copied()
+    }
+
+    // 设置 field 的过期时刻，field 不存在时返回 false
+    pub fn set_expire(&mut self, field: &[u8], at: u64) -> bool {
+        self.expire_if_needed(field);
+        if !self.fields.contains_key(field) {
+            return false;
+        }
+        self.clear_expire(field);
+        self.expires.insert(field.to_vec(), at);
+        self.expire_order.insert((at, field.to_vec()));
+        true
+    }
+
+    // 清除 field 的过期时间，返回原来是否设置了过期时间
+    pub fn persist(&mut self, field: &[u8]) -> bool {
+        self.expire_if_needed(field);
+        self.clear_expire(field).is_some()
+    }
+
+    // 是否有 field 设置了过期时间
+    pub fn has_expires(&self) -> bool {
+        !self.expires.is_empty()
+    }
+
+    // 所有 field 都已过期，此时整个哈希表视为不存在，只需要看最晚的过期时刻
+    pub fn all_expired(&self, now: u64) -> bool {
+        !self.fields.is_empty()
+            && self.expires.len() == self.fields.len()
+            && self.expire_order.last().is_some_and(|(at, _)| *at <= now)
+    }
+
+    // 按过期时刻从早到晚删除所有已过期的 field，返回删除的数量
+    pub fn remove_expired(&mut self) -> usize {
+        let now = now_millis();
+        let mut removed = 0;
+        while let Some((at, _)) = self.expire_order.first()
+            && *at <= now
+        {
+            let Some((_, field)) = self.expire_order.pop_first() else {
+                break;
+            };
+            self.expires.remove(&field);
+            self.fields.swap_remove(&field);
+            removed += 1;
+        }
+        removed
+    }
+}
+
+// 集合
+#[derive(Clone, Default)]
+pub struct SetValue {
+    // 可以按下标访问，SSCAN 的游标就是下标
+    members: IndexSet<Vec<u8>>,
+}
+
+impl SetValue {
+    pub fn new() -> Self {
+        SetValue::default()
+    }
+
+    // 加入成员，返回是否为新成员
+    pub fn insert(&mut self, member: Vec<u8>) -> bool {
+        self.members.insert(member)
+    }
+
+    // 删除成员，返回是否存在
+    pub fn remove(&mut self, member: &[u8]) -> bool {
+        self.members.swap_remove(member)
+    }
+
+    pub fn contains(&self, member: &[u8]) -> bool {
+        self.members.contains(member)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Vec<u8>> {
+        self.members.iter()
+    }
+
+    // 从 cursor 开始取出 count 个成员，返回下一次的游标
+    pub fn scan(&self, cursor: u64, count: usize) -> (u64, Vec<&Vec<u8>>) {
+        let (range, next) = scan_range(self.members.len(), cursor, count);
+        let members = range
+            .filter_map(|index| self.members.get_index(index))
+            .collect();
+        (next, members)
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+}
+
+impl FromIterator<Vec<u8>> for SetValue {
+    fn from_iter<I: IntoIterator<Item = Vec<u8>>>(iter: I) -> Self {
+        let mut set = SetValue::new();
+        for member in iter {
+            set.insert(member);
+        }
+        set
+    }
+}
+
+// 有序集合，跳表按 (分数, 成员) 排序，哈希表用于按成员查分数
+#[derive(Default)]
+pub struct SortedSet {
+    pub skiplist: OrderedSkipList<(f64, Vec<u8>)>,
+    // 可以按下标访问，ZSCAN 的游标就是下标
+    pub scores: IndexMap<Vec<u8>, f64>,
+}
+
+impl SortedSet {
+    pub fn new() -> Self {
+        SortedSet::default()
+    }
+
+    // 插入或更新成员的分数，返回是否为新成员
+    pub fn insert(&mut self, score: f64, member: Vec<u8>) -> bool {
+        let added = match self.scores.insert(member.clone(), score) {
+            // 成员已存在时先从跳表中移除旧的分数
+            Some(old_score) => {
+                self.skiplist.remove(&(old_score, member.clone()));
+                false
+            }
+            None => true,
+        };
+        self.skiplist.insert((score, member));
+        added
+    }
+
+    // 删除成员，返回是否存在
+    pub fn remove(&mut self, member: &[u8]) -> bool {
+        match self.scores.swap_remove(member) {
+            Some(score) => {
+                self.skiplist.remove(&(score, member.to_vec()));
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn score(&self, member: &[u8]) -> Option<f64> {
+        self.scores.get(member).copied()
+    }
+
+    // 从 cursor 开始取出 count 个成员，返回下一次的游标以及成员和分数
+    pub fn scan(&self, cursor: u64, count: usize) -> (u64, Vec<(&Vec<u8>, f64)>) {
+        let (range, next) = scan_range(self.scores.len(), cursor, count);
+        let members = range
+            .filter_map(|index| self.scores.get_index(index))
+            .map(|(member, score)| (member, *score))
+            .collect();
+        (next, members)
+    }
+
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+}
+
+// 值的具体类型与 Value 之间的转换，用于按类型读取 key
+pub trait ValueType: Sized {
+    fn from_value(value: &Value) -> Option<&Self>;
+    fn from_value_mut(value: &mut Value) -> Option<&mut Self>;
+    fn into_value(self) -> Value;
+}
+
+// 跳表没有实现 Clone，按顺序重新插入一遍
+impl Clone for SortedSet {
+    fn clone(&self) -> Self {
+        let mut skiplist = OrderedSkipList::new();
+        for item in self.skiplist.iter() {
+            skiplist.insert(item.clone());
+        }
+        SortedSet {
+            skiplist,
+            scores: self.scores.clone(),
+        }
+    }
+}
+
+impl ValueType for Vec<u8> {
+    fn from_value(value: &Value) -> Option<&Self> {
+        match value {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn from_value_mut(value: &mut Value) -> Option<&mut Self> {
+        match value {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> Value {
+        Value::String(self)
+    }
+}
+
+impl ValueType for HashValue {
+    fn from_value(value: &Value) -> Option<&Self> {
+        match value {
+            Value::Hash(hash) => Some(hash),
+            _ => None,
+        }
+    }
+
+    fn from_value_mut(value: &mut Value) -> Option<&mut Self> {
+        match value {
+            Value::Hash(hash) => Some(hash),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> Value {
+        Value::Hash(self)
+    }
+}
+
+impl ValueType for ListValue {
+    fn from_value(value: &Value) -> Option<&Self> {
+        match value {
+            Value::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    fn from_value_mut(value: &mut Value) -> Option<&mut Self> {
+        match value {
+            Value::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> Value {
+        Value::List(self)
+    }
+}
+
+impl ValueType for SetValue {
+    fn from_value(value: &Value) -> Option<&Self> {
+        match value {
+            Value::Set(set) => Some(set),
+            _ => None,
+        }
+    }
+
+    fn from_value_mut(value: &mut Value) -> Option<&mut Self> {
+        match value {
+            Value::Set(set) => Some(set),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> Value {
+        Value::Set(self)
+    }
+}
+
+impl ValueType for SortedSet {
+    fn from_value(value: &Value) -> Option<&Self> {
+        match value {
+            Value::ZSet(zset) => Some(zset),
+            _ => None,
+        }
+    }
+
+    fn from_value_mut(value: &mut Value) -> Option<&mut Self> {
+        match value {
+            Value::ZSet(zset) => Some(zset),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> Value {
+        Value::ZSet(self)
+    }
+}
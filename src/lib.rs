@@ -1,3 +1,7 @@
+pub mod command;
 pub mod config;
+pub mod connection;
+pub mod db;
 pub mod log;
 pub mod operations;
+pub mod protocol;
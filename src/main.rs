@@ -1,45 +1,14 @@
-mod config;
-mod log;
-mod operations;
-
 use ::log::{error, info};
-use config::reader::reader;
-use operations::hash::ops::{
-    handle_hdel_command, handle_hget_command, handle_hgetall_command, handle_hset_command,
-};
-use operations::list::ops::{
-    handle_lpop_command, handle_lpush_command, handle_lrange_command, handle_rpop_command,
-    handle_rpush_command,
-};
-use operations::set::ops::{
-    handle_sadd_command, handle_sismember_command, handle_smembers_command, handle_srem_command,
-};
-use operations::sorted_set::ops::{
-    handle_zadd_command, handle_zrange_command, handle_zrem_command, handle_zscore_command,
-};
-use operations::string::ops::{
-    handle_del_command, handle_get_command, handle_set_command
-};
-use operations::bitmap::ops::{
-    handle_setbit_command,handle_getbit_command,handle_bitcount_command
-};
-use skiplist::OrderedSkipList;
-use std::collections::{HashMap, HashSet, VecDeque};
-use std::sync::Arc;
-use bitvec::prelude::BitVec;
+use rudis::command::spec::CommandFlag;
+use rudis::command::table::{COMMAND_TABLE, execute_command};
+use rudis::config::reader::reader;
+use rudis::connection::client::Client;
+use rudis::db::expire::active_expire;
+use rudis::db::storage::Storage;
+use rudis::protocol::decoder::Decoder;
+use rudis::protocol::encoder::encode;
+use rudis::protocol::frame::Frame;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::sync::Mutex;
-
-#[derive(Clone)]
-struct Storage {
-    string_storage: Arc<Mutex<HashMap<String, String>>>,
-    hash_storage: Arc<Mutex<HashMap<String, HashMap<String, String>>>>,
-    list_storage: Arc<Mutex<HashMap<String, VecDeque<String>>>>,
-    set_storage: Arc<Mutex<HashMap<String, HashSet<String>>>>,
-    sorted_set_storage: Arc<Mutex<HashMap<String, OrderedSkipList<(f64, String)>>>>, // 跳表
-    hash_sorted_set_storage: Arc<Mutex<HashMap<String, HashMap<String, f64>>>>, // 存储zset的成员分数键值对
-    bitmap_storage:Arc<Mutex<HashMap<String,BitVec>>>
-}
 
 #[tokio::main]
 async fn main() {
@@ -55,7 +24,7 @@ async fn main() {
     info!("读取配置文件成功");
 
     // 初始化日志库
-    if let Err(e) = log::init::setup_logger() {
+    if let Err(e) = rudis::log::init::setup_logger() {
         error!("初始化日志库失败,{}", e);
         std::process::exit(1);
     }
@@ -66,35 +35,8 @@ async fn main() {
         .await
         .unwrap();
 
-    //  string类型存储
-    let hash_table_string: Arc<Mutex<HashMap<String, String>>> =
-        Arc::new(Mutex::new(HashMap::new()));
-    // hash类型存储
-    let hash_table_hash: Arc<Mutex<HashMap<String, HashMap<String, String>>>> =
-        Arc::new(Mutex::new(HashMap::new()));
-    // list类型存储
-    let hash_table_list: Arc<Mutex<HashMap<String, VecDeque<String>>>> =
-        Arc::new(Mutex::new(HashMap::new()));
-    // set类型存储
-    let hash_table_set: Arc<Mutex<HashMap<String, HashSet<String>>>> =
-        Arc::new(Mutex::new(HashMap::new()));
-    // sorted_set类型存储
-    let hash_table_sorted_set: Arc<Mutex<HashMap<String, OrderedSkipList<(f64, String)>>>> =
-        Arc::new(Mutex::new(HashMap::new()));
-    // hash_sorted_set类型存储
-    let hash_table_hash_sorted_set: Arc<Mutex<HashMap<String, HashMap<String, f64>>>> =
-        Arc::new(Mutex::new(HashMap::new()));
-    let hash_bitmap_storage: Arc<Mutex<HashMap<String,BitVec>>> =
-        Arc::new(Mutex::new(HashMap::new()));
-    let storage = Storage {
-        string_storage: hash_table_string,
-        hash_storage: hash_table_hash,
-        list_storage: hash_table_list,
-        set_storage: hash_table_set,
-        sorted_set_storage: hash_table_sorted_set,
-        hash_sorted_set_storage: hash_table_hash_sorted_set,
-        bitmap_storage: hash_bitmap_storage,
-    };
+    let storage = Storage::new();
+    tokio::spawn(active_expire(storage.clone()));
 
     loop {
         let (mut socket, addr) = listener.accept().await.unwrap();
@@ -110,147 +52,71 @@ async fn main() {
 
 // 处理客户端连接的逻辑
 async fn handle_client(socket: &mut tokio::net::TcpStream, storage: Storage) {
+    let mut client = Client::new(storage);
+    let mut decoder = Decoder::new();
+    let mut buffer = [0; 16 * 1024];
     loop {
-        let mut buffer = [0; 1024];
         match socket.read(&mut buffer).await {
             Ok(0) => {
                 info!("客户端关闭");
                 return;
             }
-            Ok(_) => {
-                let command = String::from_utf8_lossy(&buffer[..]);
-                let command = command.to_lowercase();
-
-                println!("{}", command);
-                return;
-
-                // // 分割命令字符串
-                // let parts: Vec<&str> = command.split_whitespace().collect();
-                //
-                // match parts.get(0) {
-                //     // 帮助
-                //     Some(&"help")=>{
-                //         operations::help::help::help(socket).await;
-                //     }
-                //     // string类型
-                //     Some(&"set") => {
-                //         handle_set_command(parts, socket, storage.string_storage.clone()).await
-                //     }
-                //     Some(&"get") => {
-                //         handle_get_command(parts, socket, storage.string_storage.clone()).await
-                //     }
-                //     Some(&"del") => {
-                //         handle_del_command(parts, socket, storage.string_storage.clone()).await
-                //     }
-                //
-                //     // hash类型
-                //     Some(&"hset") => {
-                //         handle_hset_command(parts, socket, storage.hash_storage.clone()).await
-                //     }
-                //     Some(&"hget") => {
-                //         handle_hget_command(parts, socket, storage.hash_storage.clone()).await
-                //     }
-                //     Some(&"hdel") => {
-                //         handle_hdel_command(parts, socket, storage.hash_storage.clone()).await
-                //     }
-                //     Some(&"hgetall") => {
-                //         handle_hgetall_command(parts, socket, storage.hash_storage.clone()).await
-                //     }
-                //
-                //     // list类型
-                //     Some(&"lpush") => {
-                //         handle_lpush_command(parts, socket, storage.list_storage.clone()).await
-                //     }
-                //     Some(&"rpush") => {
-                //         handle_rpush_command(parts, socket, storage.list_storage.clone()).await
-                //     }
-                //     Some(&"lpop") => {
-                //         handle_lpop_command(parts, socket, storage.list_storage.clone()).await
-                //     }
-                //     Some(&"rpop") => {
-                //         handle_rpop_command(parts, socket, storage.list_storage.clone()).await
-                //     }
-                //     Some(&"lrange") => {
-                //         handle_lrange_command(parts, socket, storage.list_storage.clone()).await
-                //     }
-                //
-                //     // set类型
-                //     Some(&"sadd") => {
-                //         handle_sadd_command(parts, socket, storage.set_storage.clone()).await
-                //     }
-                //     Some(&"sismember") => {
-                //         handle_sismember_command(parts, socket, storage.set_storage.clone()).await
-                //     }
-                //     Some(&"smembers") => {
-                //         handle_smembers_command(parts, socket, storage.set_storage.clone()).await
-                //     }
-                //     Some(&"srem") => {
-                //         handle_srem_command(parts, socket, storage.set_storage.clone()).await
-                //     }
-                //
-                //     // sorted_set类型
-                //     Some(&"zadd") => {
-                //         handle_zadd_command(
-                //             parts,
-                //             socket,
-                //             storage.sorted_set_storage.clone(),
-                //             storage.hash_sorted_set_storage.clone(),
-                //         )
-                //         .await
-                //     }
-                //     Some(&"zrange") => {
-                //         handle_zrange_command(parts, socket, storage.sorted_set_storage.clone())
-                //             .await
-                //     }
-                //     Some(&"zrem") => {
-                //         handle_zrem_command(
-                //             parts,
-                //             socket,
-                //             storage.sorted_set_storage.clone(),
-                //             storage.hash_sorted_set_storage.clone(),
-                //         )
-                //         .await
-                //     }
-                //     Some(&"zscore") => {
-                //         handle_zscore_command(
-                //             parts,
-                //             socket,
-                //             storage.hash_sorted_set_storage.clone(),
-                //         )
-                //         .await
-                //     }
-                //
-                //     // bitmap类型
-                //     Some(&"setbit")=>{
-                //         handle_setbit_command(
-                //             parts,
-                //             socket,
-                //             storage.bitmap_storage.clone(),
-                //         )
-                //             .await
-                //     }
-                //     Some(&"getbit")=>{
-                //         handle_getbit_command(
-                //             parts,
-                //             socket,
-                //             storage.bitmap_storage.clone(),
-                //         )
-                //             .await
-                //     }
-                //     Some(&"bitcount")=>{
-                //         handle_bitcount_command(
-                //             parts,
-                //             socket,
-                //             storage.bitmap_storage.clone(),
-                //         )
-                //             .await
-                //     }
-                //     _ => {
-                //         let response = "未定义的指令类型";
-                //         error!("{}",response);
-                //         socket.write_all(response.as_bytes()).await.unwrap();
-                //     }
-                // }
+            Ok(n) => {
+                decoder.extend(&buffer[..n]);
+                // 一次读取可能包含多个完整的帧（流水线），也可能只有半个帧，
+                // 按顺序执行所有完整的命令，回复合并后一次性写回
+                let mut out = Vec::new();
+                let mut closing = false;
+                loop {
+                    let args = match decoder.next_command() {
+                        Ok(Some(args)) => args,
+                        Ok(None) => break,
+                        Err(e) => {
+                            error!("协议解析失败,{}", e);
+                            encode(&Frame::error(format!("ERR {}", e)), client.protocol, &mut out);
+                            closing = true;
+                            break;
+                        }
+                    };
+                    // 空数组直接忽略
+                    if args.is_empty() {
+                        continue;
+                    }
+                    let blocking = COMMAND_TABLE
+                        .lookup(&args[0])
+                        .is_some_and(|spec| spec.has_flag(CommandFlag::Blocking));
+                    let response = if blocking {
+                        // 阻塞前先把之前的回复发出去，阻塞期间客户端断开时放弃执行，
+                        // 期间收到的数据留在 decoder 中，等命令返回后再处理
+                        if !out.is_empty() {
+                            if let Err(e) = socket.write_all(&out).await {
+                                error!("向客户端发送消息失败,{}", e);
+                                return;
+                            }
+                            out.clear();
+                        }
+                        tokio::select! {
+                            response = execute_command(args, &mut client) => response,
+                            _ = wait_closed(socket, &mut decoder) => {
+                                info!("客户端关闭");
+                                return;
+                            }
+                        }
+                    } else {
+                        execute_command(args, &mut client).await
+                    };
+                    encode(&response, client.protocol, &mut out);
+                }
+                if !out.is_empty()
+                    && let Err(e) = socket.write_all(&out).await
+                {
+                    error!("向客户端发送消息失败,{}", e);
+                    return;
+                }
+                // 协议错误后无法再确定下一个帧的边界，直接关闭连接
+                if closing {
+                    return;
+                }
             }
             Err(e) => {
                 error!("从服务端读取消息失败,{}", e);
@@ -259,3 +125,14 @@ async fn handle_client(socket: &mut tokio::net::TcpStream, storage: Storage) {
         }
     }
 }
+
+// 阻塞命令执行期间继续读取客户端数据，直到连接关闭或出错才返回
+async fn wait_closed(socket: &mut tokio::net::TcpStream, decoder: &mut Decoder) {
+    let mut buffer = [0; 16 * 1024];
+    loop {
+        match socket.read(&mut buffer).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => decoder.extend(&buffer[..n]),
+        }
+    }
+}
@@ -1,104 +1,126 @@
-use std::collections::HashMap;
-use std::sync::Arc;
-use bitvec::prelude::BitVec;
+use crate::command::spec::CommandFlag::{Fast, ReadOnly, Write};
+use crate::command::spec::{CommandGroup, CommandSpec};
+use crate::command::table::CommandTable;
+use crate::connection::client::Client;
+use crate::operations::utils::parse_number;
+use crate::protocol::decoder::MAX_BULK_LEN;
+use crate::protocol::frame::Frame;
 use log::error;
-use tokio::io::AsyncWriteExt;
-use tokio::sync::Mutex;
 
-const MAX_OFFSET: usize = 1024;
+// 位图就是普通的字符串，最大偏移量受字符串的最大长度限制
+const MAX_OFFSET: usize = MAX_BULK_LEN as usize * 8;
 
-// 处理 SETBIT 命令
+// 注册 bitmap 类型的命令
+pub fn register(table: &mut CommandTable) {
+    table.register(
+        CommandSpec::new("setbit", 4, &[Write], handle_setbit_command)
+            .keys(1, 1, 1)
+            .syntax("key offset value")
+            .doc(
+                CommandGroup::Bitmap,
+                "2.2.0",
+                "O(1)",
+                "Sets or clears the bit at offset of the string value. Creates the key if it doesn't exist.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("getbit", 3, &[ReadOnly, Fast], handle_getbit_command)
+            .keys(1, 1, 1)
+            .syntax("key offset")
+            .doc(
+                CommandGroup::Bitmap,
+                "2.2.0",
+                "O(1)",
+                "Returns a bit value by offset.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("bitcount", 2, &[ReadOnly], handle_bitcount_command)
+            .keys(1, 1, 1)
+            .syntax("key")
+            .doc(
+                CommandGroup::Bitmap,
+                "2.6.0",
+                "O(N)",
+                "Counts the number of set bits (population counting) in a string.",
+            ),
+    );
+}
+
+// 解析位偏移量，超出字符串的最大长度时返回 None
+fn parse_offset(data: &[u8]) -> Option<usize> {
+    parse_number::<usize>(data).filter(|offset| *offset < MAX_OFFSET)
+}
+
+// 与 Redis 一致，每个字节内从最高位开始编号，返回 (字节下标, 位掩码)
+fn bit_position(offset: usize) -> (usize, u8) {
+    (offset >> 3, 1 << (7 - (offset & 7)))
+}
+
+// 处理 SETBIT 命令，返回该位原来的值
 pub async fn handle_setbit_command(
-    parts: Vec<&str>,
-    socket: &mut tokio::net::TcpStream,
-    hash_table: Arc<Mutex<HashMap<String,BitVec>>>,
-){
-    if parts.len() != 4 {
-        error!("命令格式不符合！");
-        socket.write_all("命令格式不符合！".as_bytes()).await.unwrap();
-        return;
+    parts: Vec<Vec<u8>>,
+    client: &mut Client,
+) -> Frame {
+    let Some(offset) = parse_offset(&parts[2]) else {
+        error!("偏移量超出范围");
+        return Frame::error("ERR bit offset is not an integer or out of range");
+    };
+    let value = match parse_number::<u8>(&parts[3]) {
+        Some(value) if value <= 1 => value == 1,
+        _ => return Frame::error("ERR bit is not an integer or out of range"),
+    };
+    let mut db = client.storage.db.lock().await;
+    let bytes = match db.get_or_default::<Vec<u8>>(&parts[1]) {
+        Ok(bytes) => bytes,
+        Err(err) => return err,
+    };
+    let (index, mask) = bit_position(offset);
+    // 字符串长度不够时用 0 补齐
+    if bytes.len() <= index {
+        bytes.resize(index + 1, 0);
     }
-
-    let key = parts[1].trim_end_matches('\0').to_string();
-    let offset = parts[2].trim_end_matches('\0').parse::<usize>().unwrap();
-    let value = parts[3].trim_end_matches('\0').parse::<u8>().unwrap();
-    let mut lock_hash = hash_table.lock().await;
-    if let Some(inner_list) = lock_hash.get_mut(&key) {
-        if offset >= inner_list.len(){
-            error!("偏移量超出范围");
-            socket.write_all("偏移量超出范围".as_bytes()).await.unwrap();
-            return;
-        }
-        if value==1{
-            inner_list.set(offset,true);
-        }else{
-            inner_list.set(offset,false);
-        }
-        socket.write_all("ok".as_bytes()).await.unwrap();
-    }else{
-        let bit_vec=BitVec::repeat(false,MAX_OFFSET);
-        lock_hash.insert(key.clone(),bit_vec);
-        lock_hash.get_mut(&key).unwrap().set(offset,value==1);
-        socket.write_all("ok".as_bytes()).await.unwrap();
+    let old = bytes[index] & mask != 0;
+    if value {
+        bytes[index] |= mask;
+    } else {
+        bytes[index] &= !mask;
     }
+    Frame::Integer(old as i64)
 }
 
 // 处理 GETBIT 命令
 pub async fn handle_getbit_command(
-    parts: Vec<&str>,
-    socket: &mut tokio::net::TcpStream,
-    hash_table: Arc<Mutex<HashMap<String,BitVec>>>,
-){
-    if parts.len() != 3 {
-        error!("命令格式不符合！");
-        socket.write_all("命令格式不符合！".as_bytes()).await.unwrap();
-        return;
-    }
-    let key = parts[1].trim_end_matches('\0').to_string();
-    let offset = parts[2].trim_end_matches('\0').parse::<usize>().unwrap();
-    let lock_hash = hash_table.lock().await;
-    if let Some(inner_list) = lock_hash.get(&key) {
-        if offset >= inner_list.len(){
-            error!("偏移量超出范围");
-            socket.write_all("偏移量超出范围".as_bytes()).await.unwrap();
-            return;
-        }
-        let res = match inner_list.get(offset) {
-            Some(bit_ref) => {
-                if *bit_ref {
-                    "1"
-                } else {
-                    "0"
-                }
-            }
-            None => {
-                "0"
-            }
-        };
-        socket.write_all(res.as_bytes()).await.unwrap();
-   }else{
-        socket.write_all("0".as_bytes()).await.unwrap();
-    }
+    parts: Vec<Vec<u8>>,
+    client: &mut Client,
+) -> Frame {
+    let Some(offset) = parse_offset(&parts[2]) else {
+        return Frame::error("ERR bit offset is not an integer or out of range");
+    };
+    let db = client.storage.db.lock().await;
+    let bytes = match db.get_as::<Vec<u8>>(&parts[1]) {
+        Ok(bytes) => bytes,
+        Err(err) => return err,
+    };
+    // 超出字符串长度的位视为 0
+    let (index, mask) = bit_position(offset);
+    let bit = bytes
+        .and_then(|bytes| bytes.get(index))
+        .is_some_and(|byte| byte & mask != 0);
+    Frame::Integer(bit as i64)
 }
 
 // 处理 BITCOUNT 命令
 pub async fn handle_bitcount_command(
-    parts: Vec<&str>,
-    socket: &mut tokio::net::TcpStream,
-    hash_table: Arc<Mutex<HashMap<String,BitVec>>>,
-){
-    if parts.len() != 2 {
-        error!("命令格式不符合！");
-        socket.write_all("命令格式不符合！".as_bytes()).await.unwrap();
-        return;
+    parts: Vec<Vec<u8>>,
+    client: &mut Client,
+) -> Frame {
+    let db = client.storage.db.lock().await;
+    match db.get_as::<Vec<u8>>(&parts[1]) {
+        Ok(Some(bytes)) => Frame::Integer(
+            bytes.iter().map(|byte| byte.count_ones() as i64).sum(),
+        ),
+        Ok(None) => Frame::Integer(0),
+        Err(err) => err,
     }
-    let key = parts[1].trim_end_matches('\0').to_string();
-    let lock_hash = hash_table.lock().await;
-    if let Some(inner_list) = lock_hash.get(&key) {
-        let count=inner_list.count_ones();
-        let res=format!("位图中1的数量是：{}",count);
-        socket.write_all(res.as_bytes()).await.unwrap();
-    }else{
-        socket.write_all("0".as_bytes()).await.unwrap();
-    }
-}
\ No newline at end of file
+}
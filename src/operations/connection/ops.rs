@@ -0,0 +1,78 @@
+use crate::command::spec::CommandFlag::Fast;
+use crate::command::spec::{CommandGroup, CommandSpec};
+use crate::command::table::CommandTable;
+use crate::connection::client::Client;
+use crate::operations::utils::parse_number;
+use crate::protocol::encoder::Protocol;
+use crate::protocol::frame::Frame;
+use log::error;
+
+// 注册连接相关的命令
+pub fn register(table: &mut CommandTable) {
+    table.register(
+        CommandSpec::new("hello", -1, &[Fast], handle_hello_command)
+            .syntax("[protover [AUTH username password] [SETNAME clientname]]")
+            .doc(
+                CommandGroup::Connection,
+                "6.0.0",
+                "O(1)",
+                "Handshakes with the Redis server.",
+            ),
+    );
+}
+
+// 处理 HELLO 命令：HELLO [protover [AUTH username password] [SETNAME clientname]]
+// 协商连接使用的协议版本，并返回服务端信息
+pub async fn handle_hello_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let mut protocol = client.protocol;
+    if let Some(version) = parts.get(1) {
+        protocol = match parse_number::<i64>(version) {
+            Some(2) => Protocol::Resp2,
+            Some(3) => Protocol::Resp3,
+            Some(_) => return Frame::error("NOPROTO unsupported protocol version"),
+            None => {
+                return Frame::error("ERR Protocol version is not an integer or out of range");
+            }
+        };
+    }
+
+    let mut name = None;
+    let mut i = 2;
+    while i < parts.len() {
+        let remaining = parts.len() - i - 1;
+        if parts[i].eq_ignore_ascii_case(b"auth") && remaining >= 2 {
+            // 未开启密码认证，只有默认用户可以通过
+            if parts[i + 1] != b"default" {
+                error!("认证失败");
+                return Frame::error(
+                    "WRONGPASS invalid username-password pair or user is disabled.",
+                );
+            }
+            i += 3;
+        } else if parts[i].eq_ignore_ascii_case(b"setname") && remaining >= 1 {
+            name = Some(parts[i + 1].clone());
+            i += 2;
+        } else {
+            return Frame::error(format!(
+                "ERR Syntax error in HELLO option '{}'",
+                String::from_utf8_lossy(&parts[i])
+            ));
+        }
+    }
+
+    client.protocol = protocol;
+    if name.is_some() {
+        client.name = name;
+    }
+
+    let field = |name: &str| Frame::Bulk(name.as_bytes().to_vec());
+    Frame::Map(vec![
+        (field("server"), field("rudis")),
+        (field("version"), field(env!("CARGO_PKG_VERSION"))),
+        (field("proto"), Frame::Integer(protocol.version())),
+        (field("id"), Frame::Integer(client.id as i64)),
+        (field("mode"), field("standalone")),
+        (field("role"), field("master")),
+        (field("modules"), Frame::Array(vec![])),
+    ])
+}
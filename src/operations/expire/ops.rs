@@ -0,0 +1,295 @@
+use crate::command::spec::CommandFlag::{Fast, ReadOnly, Write};
+use crate::command::spec::{CommandGroup, CommandSpec};
+use crate::command::table::CommandTable;
+use crate::connection::client::Client;
+use crate::db::storage::now_millis;
+use crate::operations::utils::{not_integer, parse_number};
+use crate::protocol::frame::Frame;
+
+// 注册过期时间相关的命令
+pub fn register(table: &mut CommandTable) {
+    table.register(
+        CommandSpec::new("expire", -3, &[Write, Fast], handle_expire_command)
+            .keys(1, 1, 1)
+            .syntax("key seconds [NX | XX | GT | LT]")
+            .doc(
+                CommandGroup::Generic,
+                "1.0.0",
+                "O(1)",
+                "Sets the expiration time of a key in seconds.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("pexpire", -3, &[Write, Fast], handle_pexpire_command)
+            .keys(1, 1, 1)
+            .syntax("key milliseconds [NX | XX | GT | LT]")
+            .doc(
+                CommandGroup::Generic,
+                "2.6.0",
+                "O(1)",
+                "Sets the expiration time of a key in milliseconds.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("expireat", -3, &[Write, Fast], handle_expireat_command)
+            .keys(1, 1, 1)
+            .syntax("key unix-time-seconds [NX | XX | GT | LT]")
+            .doc(
+                CommandGroup::Generic,
+                "1.2.0",
+                "O(1)",
+                "Sets the expiration time of a key to a Unix timestamp.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("pexpireat", -3, &[Write, Fast], handle_pexpireat_command)
+            .keys(1, 1, 1)
+            .syntax("key unix-time-milliseconds [NX | XX | GT | LT]")
+            .doc(
+                CommandGroup::Generic,
+                "2.6.0",
+                "O(1)",
+                "Sets the expiration time of a key to a Unix milliseconds timestamp.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("ttl", 2, &[ReadOnly, Fast], handle_ttl_command)
+            .keys(1, 1, 1)
+            .syntax("key")
+            .doc(
+                CommandGroup::Generic,
+                "1.0.0",
+                "O(1)",
+                "Returns the expiration time in seconds of a key.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("pttl", 2, &[ReadOnly, Fast], handle_pttl_command)
+            .keys(1, 1, 1)
+            .syntax("key")
+            .doc(
+                CommandGroup::Generic,
+                "2.6.0",
+                "O(1)",
+                "Returns the expiration time in milliseconds of a key.",
+            ),
+    );
+    table.register(
+        CommandSpec::new(
+            "expiretime",
+            2,
+            &[ReadOnly, Fast],
+            handle_expiretime_command,
+        )
+        .keys(1, 1, 1)
+        .syntax("key")
+        .doc(
+            CommandGroup::Generic,
+            "7.0.0",
+            "O(1)",
+            "Returns the expiration time of a key as a Unix timestamp.",
+        ),
+    );
+    table.register(
+        CommandSpec::new(
+            "pexpiretime",
+            2,
+            &[ReadOnly, Fast],
+            handle_pexpiretime_command,
+        )
+        .keys(1, 1, 1)
+        .syntax("key")
+        .doc(
+            CommandGroup::Generic,
+            "7.0.0",
+            "O(1)",
+            "Returns the expiration time of a key as a Unix milliseconds timestamp.",
+        ),
+    );
+    table.register(
+        CommandSpec::new("persist", 2, &[Write, Fast], handle_persist_command)
+            .keys(1, 1, 1)
+            .syntax("key")
+            .doc(
+                CommandGroup::Generic,
+                "2.2.0",
+                "O(1)",
+                "Removes the expiration time of a key.",
+            ),
+    );
+}
+
+// EXPIRE 系列命令的 NX | XX | GT | LT 选项，XX 可以与 GT 或 LT 同时使用
+#[derive(Default, Clone, Copy)]
+pub struct ExpireFlags {
+    // 只在没有过期时间时设置
+    nx: bool,
+    // 只在已有过期时间时设置
+    xx: bool,
+    // 只在新的过期时刻更晚时设置，没有过期时间视为永不过期
+    gt: bool,
+    // 只在新的过期时刻更早时设置
+    lt: bool,
+}
+
+impl ExpireFlags {
+    pub fn parse(args: &[Vec<u8>]) -> Result<ExpireFlags, Frame> {
+        let mut flags = ExpireFlags::default();
+        for arg in args {
+            let option = String::from_utf8_lossy(arg).to_lowercase();
+            match option.as_str() {
+                "nx" => flags.nx = true,
+                "xx" => flags.xx = true,
+                "gt" => flags.gt = true,
+                "lt" => flags.lt = true,
+                _ => {
+                    return Err(Frame::error(format!(
+                        "ERR Unsupported option {}",
+                        String::from_utf8_lossy(arg)
+                    )));
+                }
+            }
+        }
+        if flags.nx && (flags.xx || flags.gt || flags.lt) {
+            return Err(Frame::error(
+                "ERR NX and XX, GT or LT options at the same time are not compatible",
+            ));
+        }
+        if flags.gt && flags.lt {
+            return Err(Frame::error(
+                "ERR GT and LT options at the same time are not compatible",
+            ));
+        }
+        Ok(flags)
+    }
+
+    // current 为当前的过期时刻，None 表示没有设置过期时间
+    pub fn allows(&self, current: Option<u64>, when: i64) -> bool {
+        let current = current.map(|at| at as i64);
+        if self.nx && current.is_some() {
+            return false;
+        }
+        if self.xx && current.is_none() {
+            return false;
+        }
+        if self.gt && current.is_none_or(|at| when <= at) {
+            return false;
+        }
+        if self.lt && current.is_some_and(|at| when >= at) {
+            return false;
+        }
+        true
+    }
+}
+
+// EXPIRE、PEXPIRE、EXPIREAT、PEXPIREAT 的公共实现
+// unit 为参数单位对应的毫秒数，relative 表示参数是相对于当前时间的时长
+async fn expire_generic(
+    parts: &[Vec<u8>],
+    client: &mut Client,
+    command: &str,
+    unit: i64,
+    relative: bool,
+) -> Frame {
+    let Some(value) = parse_number::<i64>(&parts[2]) else {
+        return not_integer();
+    };
+    let flags = match ExpireFlags::parse(&parts[3..]) {
+        Ok(flags) => flags,
+        Err(err) => return err,
+    };
+    let now = now_millis() as i64;
+    let base = if relative { now } else { 0 };
+    let Some(when) = value.checked_mul(unit).and_then(|ms| ms.checked_add(base)) else {
+        return Frame::error(format!("ERR invalid expire time in '{}' command", command));
+    };
+
+    let mut db = client.storage.db.lock().await;
+    if !db.contains_key(&parts[1]) {
+        return Frame::Integer(0);
+    }
+    if !flags.allows(db.expire_time(&parts[1]), when) {
+        return Frame::Integer(0);
+    }
+    // 过期时刻已经过去，直接删除
+    if when <= now {
+        db.remove(&parts[1]);
+    } else {
+        db.set_expire(&parts[1], when as u64);
+    }
+    Frame::Integer(1)
+}
+
+// 处理 EXPIRE 命令
+pub async fn handle_expire_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    expire_generic(&parts, client, "expire", 1000, true).await
+}
+
+// 处理 PEXPIRE 命令
+pub async fn handle_pexpire_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    expire_generic(&parts, client, "pexpire", 1, true).await
+}
+
+// 处理 EXPIREAT 命令
+pub async fn handle_expireat_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    expire_generic(&parts, client, "expireat", 1000, false).await
+}
+
+// 处理 PEXPIREAT 命令
+pub async fn handle_pexpireat_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    expire_generic(&parts, client, "pexpireat", 1, false).await
+}
+
+// 剩余生存时间，key 不存在返回 -2，没有过期时间返回 -1
+async fn ttl_generic(parts: &[Vec<u8>], client: &mut Client, unit: u64) -> Frame {
+    let db = client.storage.db.lock().await;
+    if !db.contains_key(&parts[1]) {
+        return Frame::Integer(-2);
+    }
+    match db.expire_time(&parts[1]) {
+        Some(at) => {
+            let ttl = at.saturating_sub(now_millis());
+            // 按秒返回时四舍五入，与 Redis 一致
+            Frame::Integer(((ttl + unit / 2) / unit) as i64)
+        }
+        None => Frame::Integer(-1),
+    }
+}
+
+// 处理 TTL 命令
+pub async fn handle_ttl_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    ttl_generic(&parts, client, 1000).await
+}
+
+// 处理 PTTL 命令
+pub async fn handle_pttl_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    ttl_generic(&parts, client, 1).await
+}
+
+// 过期时刻，key 不存在返回 -2，没有过期时间返回 -1
+async fn expiretime_generic(parts: &[Vec<u8>], client: &mut Client, unit: u64) -> Frame {
+    let db = client.storage.db.lock().await;
+    if !db.contains_key(&parts[1]) {
+        return Frame::Integer(-2);
+    }
+    match db.expire_time(&parts[1]) {
+        Some(at) => Frame::Integer((at / unit) as i64),
+        None => Frame::Integer(-1),
+    }
+}
+
+// 处理 EXPIRETIME 命令
+pub async fn handle_expiretime_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    expiretime_generic(&parts, client, 1000).await
+}
+
+// 处理 PEXPIRETIME 命令
+pub async fn handle_pexpiretime_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    expiretime_generic(&parts, client, 1).await
+}
+
+// 处理 PERSIST 命令，返回是否清除了过期时间
+pub async fn handle_persist_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let mut db = client.storage.db.lock().await;
+    Frame::Integer(db.persist(&parts[1]) as i64)
+}
@@ -0,0 +1,388 @@
+use crate::command::spec::CommandFlag::{Fast, ReadOnly, Write};
+use crate::command::spec::{CommandGroup, CommandSpec};
+use crate::command::table::CommandTable;
+use crate::connection::client::Client;
+use crate::operations::glob::glob_match;
+use crate::operations::scan::{ScanOptions, scan_reply};
+use crate::operations::utils::{not_integer, parse_number};
+use crate::protocol::frame::Frame;
+
+// 注册与数据类型无关的 key 操作命令
+pub fn register(table: &mut CommandTable) {
+    table.register(
+        CommandSpec::new("del", -2, &[Write], handle_del_command)
+            .keys(1, -1, 1)
+            .syntax("key [key ...]")
+            .doc(
+                CommandGroup::Generic,
+                "1.0.0",
+                "O(N) where N is the number of keys that will be removed.",
+                "Deletes one or more keys.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("unlink", -2, &[Write, Fast], handle_unlink_command)
+            .keys(1, -1, 1)
+            .syntax("key [key ...]")
+            .doc(
+                CommandGroup::Generic,
+                "4.0.0",
+                "O(1) for each key removed regardless of its size.",
+                "Asynchronously deletes one or more keys.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("exists", -2, &[ReadOnly, Fast], handle_exists_command)
+            .keys(1, -1, 1)
+            .syntax("key [key ...]")
+            .doc(
+                CommandGroup::Generic,
+                "1.0.0",
+                "O(N) where N is the number of keys to check.",
+                "Determines whether one or more keys exist.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("type", 2, &[ReadOnly, Fast], handle_type_command)
+            .keys(1, 1, 1)
+            .syntax("key")
+            .doc(
+                CommandGroup::Generic,
+                "1.0.0",
+                "O(1)",
+                "Determines the type of value stored at a key.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("rename", 3, &[Write], handle_rename_command)
+            .keys(1, 2, 1)
+            .syntax("key newkey")
+            .doc(
+                CommandGroup::Generic,
+                "1.0.0",
+                "O(1)",
+                "Renames a key and overwrites the destination.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("renamenx", 3, &[Write, Fast], handle_renamenx_command)
+            .keys(1, 2, 1)
+            .syntax("key newkey")
+            .doc(
+                CommandGroup::Generic,
+                "1.0.0",
+                "O(1)",
+                "Renames a key only when the target key name doesn't exist.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("copy", -3, &[Write], handle_copy_command)
+            .keys(1, 2, 1)
+            .syntax("source destination [DB destination-db] [REPLACE]")
+            .doc(
+                CommandGroup::Generic,
+                "6.2.0",
+                "O(N) worst case for collections, where N is the number of nested items. O(1) for string values.",
+                "Copies the value of a key to a new key.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("touch", -2, &[ReadOnly, Fast], handle_touch_command)
+            .keys(1, -1, 1)
+            .syntax("key [key ...]")
+            .doc(
+                CommandGroup::Generic,
+                "3.2.1",
+                "O(N) where N is the number of keys that will be touched.",
+                "Returns the number of existing keys out of those specified after updating the time they were last accessed.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("randomkey", 1, &[ReadOnly], handle_randomkey_command).doc(
+            CommandGroup::Generic,
+            "1.0.0",
+            "O(N) where N is the number of keys in the database.",
+            "Returns a random key name from the database.",
+        ),
+    );
+    table.register(
+        CommandSpec::new("keys", 2, &[ReadOnly], handle_keys_command)
+            .syntax("pattern")
+            .doc(
+                CommandGroup::Generic,
+                "1.0.0",
+                "O(N) with N being the number of keys in the database.",
+                "Returns all key names that match a pattern.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("scan", -2, &[ReadOnly], handle_scan_command)
+            .syntax("cursor [MATCH pattern] [COUNT count] [TYPE type]")
+            .doc(
+                CommandGroup::Generic,
+                "2.8.0",
+                "O(log N + COUNT) for every call. O(N) for a complete iteration.",
+                "Iterates over the key names in the database.",
+            ),
+    );
+}
+
+// 处理 DEL 命令，无论 key 是什么类型都会删除，返回被删除的key数量
+pub async fn handle_del_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let mut db = client.storage.db.lock().await;
+    let removed = parts[1..]
+        .iter()
+        .filter(|key| db.remove(key).is_some())
+        .count();
+    Frame::Integer(removed as i64)
+}
+
+// 处理 UNLINK 命令，与 DEL 相同，但被删除的值在释放锁之后才回收，大集合不会阻塞其他客户端
+pub async fn handle_unlink_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let mut removed = Vec::new();
+    {
+        let mut db = client.storage.db.lock().await;
+        for key in &parts[1..] {
+            if let Some(value) = db.remove(key) {
+                removed.push(value);
+            }
+        }
+    }
+    let count = removed.len();
+    drop(removed);
+    Frame::Integer(count as i64)
+}
+
+// 处理 EXISTS 命令，重复的 key 会被重复计数
+pub async fn handle_exists_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let db = client.storage.db.lock().await;
+    let count = parts[1..].iter().filter(|key| db.contains_key(key)).count();
+    Frame::Integer(count as i64)
+}
+
+// 处理 TYPE 命令，key 不存在时返回 none
+pub async fn handle_type_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let db = client.storage.db.lock().await;
+    let name = db.get(&parts[1]).map_or("none", |value| value.type_name());
+    Frame::Simple(name.to_string())
+}
+
+// 处理 RENAME 命令
+pub async fn handle_rename_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let mut db = client.storage.db.lock().await;
+    if !db.contains_key(&parts[1]) {
+        return Frame::error("ERR no such key");
+    }
+    // 新旧 key 相同时什么都不做
+    if parts[1] != parts[2] {
+        db.rename(&parts[1], parts[2].clone());
+    }
+    Frame::ok()
+}
+
+// 处理 RENAMENX 命令，newkey 已存在时不做修改并返回 0
+pub async fn handle_renamenx_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let mut db = client.storage.db.lock().await;
+    if !db.contains_key(&parts[1]) {
+        return Frame::error("ERR no such key");
+    }
+    if db.contains_key(&parts[2]) {
+        return Frame::Integer(0);
+    }
+    db.rename(&parts[1], parts[2].clone());
+    Frame::Integer(1)
+}
+
+// 处理 COPY 命令，只有一个数据库，DB 参数只接受 0
+pub async fn handle_copy_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let mut replace = false;
+    let mut i = 3;
+    while i < parts.len() {
+        if parts[i].eq_ignore_ascii_case(b"replace") {
+            replace = true;
+        } else if parts[i].eq_ignore_ascii_case(b"db") && i + 1 < parts.len() {
+            i += 1;
+            match parse_number::<i64>(&parts[i]) {
+                Some(0) => {}
+                Some(_) => return Frame::error("ERR DB index is out of range"),
+                None => return not_integer(),
+            }
+        } else {
+            return Frame::error("ERR syntax error");
+        }
+        i += 1;
+    }
+    if parts[1] == parts[2] {
+        return Frame::error("ERR source and destination objects are the same");
+    }
+
+    let mut db = client.storage.db.lock().await;
+    let Some(value) = db.get(&parts[1]).cloned() else {
+        return Frame::Integer(0);
+    };
+    if !replace && db.contains_key(&parts[2]) {
+        return Frame::Integer(0);
+    }
+    // 过期时间随值一起复制
+    let expire = db.expire_time(&parts[1]);
+    db.insert(parts[2].clone(), value);
+    if let Some(at) = expire {
+        db.set_expire(&parts[2], at);
+    }
+    Frame::Integer(1)
+}
+
+// 处理 TOUCH 命令，返回存在的 key 数量
+pub async fn handle_touch_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let db = client.storage.db.lock().await;
+    let count = parts[1..].iter().filter(|key| db.contains_key(key)).count();
+    Frame::Integer(count as i64)
+}
+
+// 处理 RANDOMKEY 命令，键空间为空时返回空值
+pub async fn handle_randomkey_command(_parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let mut db = client.storage.db.lock().await;
+    match db.random_key() {
+        Some(key) => Frame::Bulk(key),
+        None => Frame::Null,
+    }
+}
+
+// 处理 KEYS 命令，返回所有匹配模式的 key
+pub async fn handle_keys_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let db = client.storage.db.lock().await;
+    Frame::bulks(
+        db.iter()
+            .filter(|(key, _)| glob_match(&parts[1], key))
+            .map(|(key, _)| key.clone()),
+    )
+}
+
+// 处理 SCAN 命令，MATCH 和 TYPE 在取出一批 key 之后再过滤，因此一次可能返回少于 COUNT 个甚至 0 个 key
+pub async fn handle_scan_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let options = match ScanOptions::parse(&parts[1..], true) {
+        Ok(options) => options,
+        Err(err) => return err,
+    };
+    let db = client.storage.db.lock().await;
+    let (cursor, keys) = db.scan(options.cursor, options.count);
+    let keys = keys
+        .into_iter()
+        .filter(|(key, value)| {
+            options.matches(key)
+                && options
+                    .type_name
+                    .as_ref()
+                    .is_none_or(|name| name == value.type_name())
+        })
+        .map(|(key, _)| Frame::Bulk(key.clone()))
+        .collect();
+    scan_reply(cursor, keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::operations::testing::{bulk, bulks, client, run};
+    use crate::protocol::frame::Frame;
+
+    #[tokio::test]
+    async fn rename_moves_value_and_ttl() {
+        let mut client = client();
+        assert_eq!(
+            run(&mut client, &["rename", "none", "b"]).await,
+            Frame::error("ERR no such key")
+        );
+        run(&mut client, &["set", "a", "1", "ex", "100"]).await;
+        run(&mut client, &["rpush", "b", "x"]).await;
+        assert_eq!(run(&mut client, &["rename", "a", "b"]).await, Frame::ok());
+        assert_eq!(run(&mut client, &["exists", "a"]).await, Frame::Integer(0));
+        assert_eq!(run(&mut client, &["get", "b"]).await, bulk("1"));
+        assert_eq!(run(&mut client, &["ttl", "b"]).await, Frame::Integer(100));
+        // 新旧 key 相同时保持原样
+        assert_eq!(run(&mut client, &["rename", "b", "b"]).await, Frame::ok());
+        assert_eq!(run(&mut client, &["get", "b"]).await, bulk("1"));
+
+        run(&mut client, &["set", "c", "2"]).await;
+        assert_eq!(
+            run(&mut client, &["renamenx", "b", "c"]).await,
+            Frame::Integer(0)
+        );
+        assert_eq!(run(&mut client, &["get", "c"]).await, bulk("2"));
+        assert_eq!(
+            run(&mut client, &["renamenx", "b", "d"]).await,
+            Frame::Integer(1)
+        );
+        assert_eq!(run(&mut client, &["get", "d"]).await, bulk("1"));
+        assert_eq!(
+            run(&mut client, &["renamenx", "b", "e"]).await,
+            Frame::error("ERR no such key")
+        );
+    }
+
+    #[tokio::test]
+    async fn copy_duplicates_value_and_ttl() {
+        let mut client = client();
+        run(&mut client, &["rpush", "src", "a", "b"]).await;
+        run(&mut client, &["expire", "src", "100"]).await;
+        assert_eq!(
+            run(&mut client, &["copy", "src", "dst"]).await,
+            Frame::Integer(1)
+        );
+        assert_eq!(run(&mut client, &["ttl", "dst"]).await, Frame::Integer(100));
+        // 副本与原值互不影响
+        run(&mut client, &["rpush", "dst", "c"]).await;
+        assert_eq!(
+            run(&mut client, &["lrange", "src", "0", "-1"]).await,
+            bulks(&["a", "b"])
+        );
+
+        run(&mut client, &["set", "s", "v"]).await;
+        assert_eq!(
+            run(&mut client, &["copy", "s", "dst"]).await,
+            Frame::Integer(0)
+        );
+        assert_eq!(
+            run(&mut client, &["copy", "s", "dst", "replace", "db", "0"]).await,
+            Frame::Integer(1)
+        );
+        assert_eq!(run(&mut client, &["get", "dst"]).await, bulk("v"));
+        assert_eq!(run(&mut client, &["ttl", "dst"]).await, Frame::Integer(-1));
+        assert_eq!(
+            run(&mut client, &["copy", "none", "x"]).await,
+            Frame::Integer(0)
+        );
+
+        assert_eq!(
+            run(&mut client, &["copy", "s", "s"]).await,
+            Frame::error("ERR source and destination objects are the same")
+        );
+        assert_eq!(
+            run(&mut client, &["copy", "s", "x", "db", "1"]).await,
+            Frame::error("ERR DB index is out of range")
+        );
+        assert_eq!(
+            run(&mut client, &["copy", "s", "x", "bogus"]).await,
+            Frame::error("ERR syntax error")
+        );
+    }
+
+    #[tokio::test]
+    async fn randomkey_skips_expired_keys() {
+        let mut client = client();
+        assert_eq!(run(&mut client, &["randomkey"]).await, Frame::Null);
+        run(&mut client, &["set", "k", "v"]).await;
+        for i in 0..10 {
+            run(
+                &mut client,
+                &["set", &format!("gone:{}", i), "v", "pxat", "1"],
+            )
+            .await;
+        }
+        for _ in 0..10 {
+            assert_eq!(run(&mut client, &["randomkey"]).await, bulk("k"));
+        }
+        assert_eq!(run(&mut client, &["keys", "*"]).await, bulks(&["k"]));
+        run(&mut client, &["del", "k"]).await;
+        assert_eq!(run(&mut client, &["randomkey"]).await, Frame::Null);
+    }
+}
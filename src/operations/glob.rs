@@ -0,0 +1,112 @@
+// Redis 风格的 glob 匹配，支持 *、?、[abc]、[^abc]、[a-z] 以及用 \ 转义
+pub fn glob_match(pattern: &[u8], string: &[u8]) -> bool {
+    let (mut p, mut s) = (0, 0);
+    // 最近一个 * 之后的模式位置，以及它目前匹配到的字符串位置，后续匹配失败时从这里回溯
+    let mut star: Option<(usize, usize)> = None;
+    while s < string.len() {
+        if pattern.get(p) == Some(&b'*') {
+            p += 1;
+            star = Some((p, s));
+            continue;
+        }
+        if p < pattern.len()
+            && let Some(next) = match_one(pattern, p, string[s])
+        {
+            p = next;
+            s += 1;
+            continue;
+        }
+        // 让最近的 * 多匹配一个字符后重试
+        match star {
+            Some((star_p, star_s)) => {
+                p = star_p;
+                s = star_s + 1;
+                star = Some((star_p, s));
+            }
+            None => return false,
+        }
+    }
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
+// 用 pattern[p..] 开头的一个记号匹配字符 c，成功时返回下一个记号的位置
+fn match_one(pattern: &[u8], p: usize, c: u8) -> Option<usize> {
+    match pattern[p] {
+        b'?' => Some(p + 1),
+        b'[' => match_class(pattern, p + 1, c),
+        b'\\' if p + 1 < pattern.len() => (pattern[p + 1] == c).then_some(p + 2),
+        other => (other == c).then_some(p + 1),
+    }
+}
+
+// 匹配 [...] 字符集合，p 指向 [ 之后的位置，没有闭合的 ] 时集合一直延续到模式末尾
+fn match_class(pattern: &[u8], mut p: usize, c: u8) -> Option<usize> {
+    let negate = pattern.get(p) == Some(&b'^');
+    if negate {
+        p += 1;
+    }
+    let mut matched = false;
+    while let Some(&token) = pattern.get(p) {
+        if token == b']' {
+            p += 1;
+            break;
+        }
+        if token == b'\\' && p + 1 < pattern.len() {
+            matched |= pattern[p + 1] == c;
+            p += 2;
+        } else if p + 2 < pattern.len() && pattern[p + 1] == b'-' {
+            let (start, end) = (token.min(pattern[p + 2]), token.max(pattern[p + 2]));
+            matched |= (start..=end).contains(&c);
+            p += 3;
+        } else {
+            matched |= token == c;
+            p += 1;
+        }
+    }
+    (matched != negate).then_some(p)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, string: &str) -> bool {
+        glob_match(pattern.as_bytes(), string.as_bytes())
+    }
+
+    #[test]
+    fn star_and_question_mark() {
+        assert!(matches("*", ""));
+        assert!(matches("*", "anything"));
+        assert!(matches("user:*", "user:42"));
+        assert!(!matches("user:*", "users:42"));
+        assert!(matches("h?llo", "hello"));
+        assert!(!matches("h?llo", "hllo"));
+        assert!(matches("a*b*c", "axxbyyc"));
+        assert!(matches("a*b*c", "abcbc"));
+        assert!(!matches("a*b*c", "axxbyy"));
+        assert!(matches("**x", "x"));
+    }
+
+    #[test]
+    fn character_classes() {
+        assert!(matches("h[ae]llo", "hallo"));
+        assert!(!matches("h[ae]llo", "hillo"));
+        assert!(matches("h[^e]llo", "hallo"));
+        assert!(!matches("h[^e]llo", "hello"));
+        assert!(matches("h[a-c]llo", "hbllo"));
+        assert!(matches("h[c-a]llo", "hbllo"));
+        assert!(!matches("h[a-c]llo", "hdllo"));
+        assert!(matches("[\\]]", "]"));
+        // 没有闭合的 ] 时集合延续到模式末尾
+        assert!(matches("x[ab", "xa"));
+    }
+
+    #[test]
+    fn escapes() {
+        assert!(matches("a\\*", "a*"));
+        assert!(!matches("a\\*", "ab"));
+        assert!(matches("\\?", "?"));
+        assert!(!matches("\\?", "x"));
+    }
+}
@@ -0,0 +1,467 @@
+use crate::command::spec::CommandFlag::{Fast, ReadOnly, Write};
+use crate::command::spec::{CommandGroup, CommandSpec};
+use crate::command::table::CommandTable;
+use crate::connection::client::Client;
+use crate::db::storage::now_millis;
+use crate::db::value::HashValue;
+use crate::operations::expire::ops::ExpireFlags;
+use crate::operations::utils::{not_integer, parse_number};
+use crate::protocol::frame::Frame;
+
+// field 过期时刻的上限，与 Redis 一致为 2^48 - 1 毫秒
+const MAX_FIELD_EXPIRE: i64 = (1 << 48) - 1;
+
+// field 不存在
+const NO_FIELD: i64 = -2;
+// field 没有设置过期时间
+const NO_EXPIRE: i64 = -1;
+
+// 注册哈希表 field 过期时间相关的命令
+pub fn register(table: &mut CommandTable) {
+    table.register(
+        CommandSpec::new("hexpire", -6, &[Write, Fast], handle_hexpire_command)
+            .keys(1, 1, 1)
+            .syntax("key seconds [NX | XX | GT | LT] FIELDS numfields field [field ...]")
+            .doc(
+                CommandGroup::Hash,
+                "7.4.0",
+                "O(N) where N is the number of specified fields",
+                "Set expiry for hash field using relative time to expire (seconds)",
+            ),
+    );
+    table.register(
+        CommandSpec::new("hpexpire", -6, &[Write, Fast], handle_hpexpire_command)
+            .keys(1, 1, 1)
+            .syntax("key milliseconds [NX | XX | GT | LT] FIELDS numfields field [field ...]")
+            .doc(
+                CommandGroup::Hash,
+                "7.4.0",
+                "O(N) where N is the number of specified fields",
+                "Set expiry for hash field using relative time to expire (milliseconds)",
+            ),
+    );
+    table.register(
+        CommandSpec::new("hexpireat", -6, &[Write, Fast], handle_hexpireat_command)
+            .keys(1, 1, 1)
+            .syntax("key unix-time-seconds [NX | XX | GT | LT] FIELDS numfields field [field ...]")
+            .doc(
+                CommandGroup::Hash,
+                "7.4.0",
+                "O(N) where N is the number of specified fields",
+                "Set expiry for hash field using an absolute Unix timestamp (seconds)",
+            ),
+    );
+    table.register(
+        CommandSpec::new("hpexpireat", -6, &[Write, Fast], handle_hpexpireat_command)
+            .keys(1, 1, 1)
+            .syntax(
+                "key unix-time-milliseconds [NX | XX | GT | LT] FIELDS numfields field [field ...]",
+            )
+            .doc(
+                CommandGroup::Hash,
+                "7.4.0",
+                "O(N) where N is the number of specified fields",
+                "Set expiry for hash field using an absolute Unix timestamp (milliseconds)",
+            ),
+    );
+    table.register(
+        CommandSpec::new("httl", -5, &[ReadOnly, Fast], handle_httl_command)
+            .keys(1, 1, 1)
+            .syntax("key FIELDS numfields field [field ...]")
+            .doc(
+                CommandGroup::Hash,
+                "7.4.0",
+                "O(N) where N is the number of specified fields",
+                "Returns the TTL in seconds of a hash field.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("hpttl", -5, &[ReadOnly, Fast], handle_hpttl_command)
+            .keys(1, 1, 1)
+            .syntax("key FIELDS numfields field [field ...]")
+            .doc(
+                CommandGroup::Hash,
+                "7.4.0",
+                "O(N) where N is the number of specified fields",
+                "Returns the TTL in milliseconds of a hash field.",
+            ),
+    );
+    table.register(
+        CommandSpec::new(
+            "hexpiretime",
+            -5,
+            &[ReadOnly, Fast],
+            handle_hexpiretime_command,
+        )
+        .keys(1, 1, 1)
+        .syntax("key FIELDS numfields field [field ...]")
+        .doc(
+            CommandGroup::Hash,
+            "7.4.0",
+            "O(N) where N is the number of specified fields",
+            "Returns the expiration time of a hash field as a Unix timestamp, in seconds.",
+        ),
+    );
+    table.register(
+        CommandSpec::new(
+            "hpexpiretime",
+            -5,
+            &[ReadOnly, Fast],
+            handle_hpexpiretime_command,
+        )
+        .keys(1, 1, 1)
+        .syntax("key FIELDS numfields field [field ...]")
+        .doc(
+            CommandGroup::Hash,
+            "7.4.0",
+            "O(N) where N is the number of specified fields",
+            "Returns the expiration time of a hash field as a Unix timestamp, in msec.",
+        ),
+    );
+    table.register(
+        CommandSpec::new("hpersist", -5, &[Write, Fast], handle_hpersist_command)
+            .keys(1, 1, 1)
+            .syntax("key FIELDS numfields field [field ...]")
+            .doc(
+                CommandGroup::Hash,
+                "7.4.0",
+                "O(N) where N is the number of specified fields",
+                "Removes the expiration time for each specified field",
+            ),
+    );
+}
+
+// 解析从 at 开始的 FIELDS numfields field [field ...]，返回 field 列表
+fn parse_fields(parts: &[Vec<u8>], at: usize) -> Result<&[Vec<u8>], Frame> {
+    if !parts
+        .get(at)
+        .is_some_and(|arg| arg.eq_ignore_ascii_case(b"fields"))
+    {
+        return Err(Frame::error(
+            "ERR Mandatory argument FIELDS is missing or not at the right position",
+        ));
+    }
+    let count = match parts
+        .get(at + 1)
+        .and_then(|count| parse_number::<i64>(count))
+    {
+        Some(count) if count > 0 => count,
+        _ => {
+            return Err(Frame::error(
+                "ERR Parameter `numFields` should be greater than 0",
+            ));
+        }
+    };
+    let fields = &parts[at + 2..];
+    if fields.len() as i64 != count {
+        return Err(Frame::error(
+            "ERR The `numfields` parameter must match the number of arguments",
+        ));
+    }
+    Ok(fields)
+}
+
+// HEXPIRE、HPEXPIRE、HEXPIREAT、HPEXPIREAT 的公共实现，每个 field 返回一个结果：
+// -2 表示 field 不存在，0 表示不满足 NX | XX | GT | LT 条件，
+// 1 表示设置成功，2 表示过期时刻已经过去、field 被直接删除
+// unit 为参数单位对应的毫秒数，relative 表示参数是相对于当前时间的时长
+async fn hexpire_generic(
+    parts: &[Vec<u8>],
+    client: &mut Client,
+    command: &str,
+    unit: i64,
+    relative: bool,
+) -> Frame {
+    let Some(value) = parse_number::<i64>(&parts[2]) else {
+        return not_integer();
+    };
+    // 条件选项是可选的，只能出现在 FIELDS 之前
+    let (flags, fields_at) = if parts[3].eq_ignore_ascii_case(b"fields") {
+        (ExpireFlags::default(), 3)
+    } else {
+        match ExpireFlags::parse(&parts[3..4]) {
+            Ok(flags) => (flags, 4),
+            Err(_) => {
+                return Frame::error(
+                    "ERR Mandatory argument FIELDS is missing or not at the right position",
+                );
+            }
+        }
+    };
+    let fields = match parse_fields(parts, fields_at) {
+        Ok(fields) => fields,
+        Err(err) => return err,
+    };
+    let now = now_millis() as i64;
+    let base = if relative { now } else { 0 };
+    let when = match value.checked_mul(unit).and_then(|ms| ms.checked_add(base)) {
+        Some(when) if value >= 0 && when <= MAX_FIELD_EXPIRE => when,
+        _ => return Frame::error(format!("ERR invalid expire time in '{}' command", command)),
+    };
+
+    let mut db = client.storage.db.lock().await;
+    let inner_map = match db.get_mut_as::<HashValue>(&parts[1]) {
+        Ok(Some(inner_map)) => inner_map,
+        Ok(None) => return Frame::Array(vec![Frame::Integer(NO_FIELD); fields.len()]),
+        Err(err) => return err,
+    };
+    let mut res = Vec::with_capacity(fields.len());
+    for field in fields {
+        if !inner_map.contains_key(field) {
+            res.push(Frame::Integer(NO_FIELD));
+        } else if !flags.allows(inner_map.expire_time(field), when) {
+            res.push(Frame::Integer(0));
+        } else if when <= now {
+            // 过期时刻已经过去，直接删除
+            inner_map.remove(field);
+            res.push(Frame::Integer(2));
+        } else {
+            inner_map.set_expire(field, when as u64);
+            res.push(Frame::Integer(1));
+        }
+    }
+    // 最后一个field被删除后，key也随之删除
+    if inner_map.is_empty() {
+        db.remove(&parts[1]);
+    } else if inner_map.has_expires() {
+        db.track_field_expires(&parts[1]);
+    }
+    Frame::Array(res)
+}
+
+// 处理 HEXPIRE 命令
+pub async fn handle_hexpire_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    hexpire_generic(&parts, client, "hexpire", 1000, true).await
+}
+
+// 处理 HPEXPIRE 命令
+pub async fn handle_hpexpire_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    hexpire_generic(&parts, client, "hpexpire", 1, true).await
+}
+
+// 处理 HEXPIREAT 命令
+pub async fn handle_hexpireat_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    hexpire_generic(&parts, client, "hexpireat", 1000, false).await
+}
+
+// 处理 HPEXPIREAT 命令
+pub async fn handle_hpexpireat_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    hexpire_generic(&parts, client, "hpexpireat", 1, false).await
+}
+
+// 按 field 读取过期信息的公共实现，field 不存在返回 -2，没有过期时间返回 -1，
+// 否则返回 reply(过期时刻)
+async fn field_expire_generic(
+    parts: &[Vec<u8>],
+    client: &mut Client,
+    reply: impl Fn(u64) -> i64,
+) -> Frame {
+    let fields = match parse_fields(parts, 2) {
+        Ok(fields) => fields,
+        Err(err) => return err,
+    };
+    let db = client.storage.db.lock().await;
+    let inner_map = match db.get_as::<HashValue>(&parts[1]) {
+        Ok(inner_map) => inner_map,
+        Err(err) => return err,
+    };
+    Frame::Array(
+        fields
+            .iter()
+            .map(|field| {
+                let Some(inner_map) = inner_map.filter(|inner_map| inner_map.contains_key(field))
+                else {
+                    return Frame::Integer(NO_FIELD);
+                };
+                match inner_map.expire_time(field) {
+                    Some(at) => Frame::Integer(reply(at)),
+                    None => Frame::Integer(NO_EXPIRE),
+                }
+            })
+            .collect(),
+    )
+}
+
+// 剩余生存时间，按秒返回时四舍五入，与 TTL 命令一致
+fn ttl(at: u64, unit: u64) -> i64 {
+    let ttl = at.saturating_sub(now_millis());
+    ((ttl + unit / 2) / unit) as i64
+}
+
+// 处理 HTTL 命令
+pub async fn handle_httl_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    field_expire_generic(&parts, client, |at| ttl(at, 1000)).await
+}
+
+// 处理 HPTTL 命令
+pub async fn handle_hpttl_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    field_expire_generic(&parts, client, |at| ttl(at, 1)).await
+}
+
+// 处理 HEXPIRETIME 命令
+pub async fn handle_hexpiretime_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    field_expire_generic(&parts, client, |at| (at / 1000) as i64).await
+}
+
+// 处理 HPEXPIRETIME 命令
+pub async fn handle_hpexpiretime_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    field_expire_generic(&parts, client, |at| at as i64).await
+}
+
+// 处理 HPERSIST 命令，field 不存在返回 -2，没有过期时间返回 -1，清除成功返回 1
+pub async fn handle_hpersist_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let fields = match parse_fields(&parts, 2) {
+        Ok(fields) => fields,
+        Err(err) => return err,
+    };
+    let mut db = client.storage.db.lock().await;
+    let inner_map = match db.get_mut_as::<HashValue>(&parts[1]) {
+        Ok(Some(inner_map)) => inner_map,
+        Ok(None) => return Frame::Array(vec![Frame::Integer(NO_FIELD); fields.len()]),
+        Err(err) => return err,
+    };
+    Frame::Array(
+        fields
+            .iter()
+            .map(|field| {
+                if !inner_map.contains_key(field) {
+                    Frame::Integer(NO_FIELD)
+                } else if inner_map.persist(field) {
+                    Frame::Integer(1)
+                } else {
+                    Frame::Integer(NO_EXPIRE)
+                }
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::operations::testing::{bulk, client, run};
+    use crate::protocol::frame::Frame;
+    use std::time::Duration;
+
+    fn integers(items: &[i64]) -> Frame {
+        Frame::Array(items.iter().map(|item| Frame::Integer(*item)).collect())
+    }
+
+    #[tokio::test]
+    async fn expired_fields_are_invisible_and_counted_out() {
+        let mut client = client();
+        run(&mut client, &["hset", "h", "a", "1", "b", "2", "c", "3"]).await;
+        assert_eq!(
+            run(
+                &mut client,
+                &["hpexpire", "h", "20", "fields", "2", "a", "b"]
+            )
+            .await,
+            integers(&[1, 1])
+        );
+        assert_eq!(run(&mut client, &["hlen", "h"]).await, Frame::Integer(3));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert_eq!(run(&mut client, &["hlen", "h"]).await, Frame::Integer(1));
+        assert_eq!(run(&mut client, &["hget", "h", "a"]).await, Frame::Null);
+        assert_eq!(
+            run(&mut client, &["hexists", "h", "b"]).await,
+            Frame::Integer(0)
+        );
+        assert_eq!(
+            run(&mut client, &["hgetall", "h"]).await,
+            Frame::Map(vec![(bulk("c"), bulk("3"))])
+        );
+
+        // 重新写入的 field 不再带有原来的过期时间
+        run(&mut client, &["hset", "h", "a", "4"]).await;
+        assert_eq!(run(&mut client, &["hlen", "h"]).await, Frame::Integer(2));
+        assert_eq!(
+            run(&mut client, &["httl", "h", "fields", "3", "a", "b", "c"]).await,
+            integers(&[-1, -2, -1])
+        );
+        assert_eq!(run(&mut client, &["hget", "h", "a"]).await, bulk("4"));
+    }
+
+    #[tokio::test]
+    async fn hash_disappears_when_all_fields_expire() {
+        let mut client = client();
+        run(&mut client, &["hset", "h", "a", "1", "b", "2"]).await;
+        assert_eq!(
+            run(
+                &mut client,
+                &["hpexpire", "h", "20", "fields", "2", "a", "b"]
+            )
+            .await,
+            integers(&[1, 1])
+        );
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(run(&mut client, &["exists", "h"]).await, Frame::Integer(0));
+        assert_eq!(run(&mut client, &["hlen", "h"]).await, Frame::Integer(0));
+
+        // 过期时刻已经过去时直接删除 field，最后一个 field 被删除后 key 随之删除
+        run(&mut client, &["hset", "g", "a", "1"]).await;
+        assert_eq!(
+            run(&mut client, &["hexpireat", "g", "1", "fields", "1", "a"]).await,
+            integers(&[2])
+        );
+        assert_eq!(run(&mut client, &["exists", "g"]).await, Frame::Integer(0));
+    }
+
+    #[tokio::test]
+    async fn conditions_and_persist_update_field_ttls() {
+        let mut client = client();
+        run(&mut client, &["hset", "h", "a", "1", "b", "2"]).await;
+        assert_eq!(
+            run(
+                &mut client,
+                &["hexpire", "h", "100", "xx", "fields", "1", "a"]
+            )
+            .await,
+            integers(&[0])
+        );
+        assert_eq!(
+            run(
+                &mut client,
+                &["hexpire", "h", "100", "nx", "fields", "2", "a", "x"]
+            )
+            .await,
+            integers(&[1, -2])
+        );
+        assert_eq!(
+            run(
+                &mut client,
+                &["hexpire", "h", "50", "gt", "fields", "1", "a"]
+            )
+            .await,
+            integers(&[0])
+        );
+        assert_eq!(
+            run(
+                &mut client,
+                &["hexpire", "h", "50", "lt", "fields", "1", "a"]
+            )
+            .await,
+            integers(&[1])
+        );
+        assert_eq!(
+            run(&mut client, &["httl", "h", "fields", "2", "a", "b"]).await,
+            integers(&[50, -1])
+        );
+        assert_eq!(
+            run(
+                &mut client,
+                &["hpersist", "h", "fields", "3", "a", "b", "x"]
+            )
+            .await,
+            integers(&[1, -1, -2])
+        );
+        assert_eq!(
+            run(&mut client, &["httl", "h", "fields", "1", "a"]).await,
+            integers(&[-1])
+        );
+        assert_eq!(
+            run(&mut client, &["hexpire", "h", "-1", "fields", "1", "a"]).await,
+            Frame::error("ERR invalid expire time in 'hexpire' command")
+        );
+    }
+}
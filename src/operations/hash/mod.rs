@@ -1 +1,2 @@
+pub mod expire;
 pub mod ops;
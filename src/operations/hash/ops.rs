@@ -1,143 +1,520 @@
-use log::error;
-use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::io::AsyncWriteExt;
-use tokio::sync::Mutex;
-
-// 检查命令格式
-fn check_command_format(parts: &[&str], expected_len: usize) -> bool {
-    parts.len() == expected_len
-}
-
-// 获取内部哈希表
-async fn get_inner_map(
-    parts: &[&str],
-    socket: &mut tokio::net::TcpStream,
-    hash_table: Arc<Mutex<HashMap<String, HashMap<String, String>>>>,
-) -> Option<HashMap<String, String>> {
-    let key = parts[1].trim_end_matches('\0').to_string();
-    let lock_hash = hash_table.lock().await;
-    match lock_hash.get(&key) {
-        Some(v) => Some(v.clone()),
-        None => {
-            error!("未找到key");
-            socket.write_all("未找到key".as_bytes()).await.unwrap();
-            None
+use crate::command::spec::CommandFlag::{Fast, ReadOnly, Write};
+use crate::command::spec::{CommandGroup, CommandSpec};
+use crate::command::table::CommandTable;
+use crate::connection::client::Client;
+use crate::db::value::HashValue;
+use crate::operations::scan::{ScanOptions, scan_reply};
+use crate::operations::utils::{
+    format_float, not_integer, parse_float, parse_integer, parse_number, wrong_args,
+};
+use crate::protocol::encoder::Protocol;
+use crate::protocol::frame::Frame;
+use rand::seq::{IteratorRandom, SliceRandom};
+
+// 注册 hash 类型的命令
+pub fn register(table: &mut CommandTable) {
+    table.register(
+        CommandSpec::new("hset", -4, &[Write, Fast], handle_hset_command)
+            .keys(1, 1, 1)
+            .syntax("key field value [field value ...]")
+            .doc(
+                CommandGroup::Hash,
+                "2.0.0",
+                "O(1) for each field/value pair added, so O(N) to add N field/value pairs when the command is called with multiple field/value pairs.",
+                "Creates or modifies the value of a field in a hash.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("hget", 3, &[ReadOnly, Fast], handle_hget_command)
+            .keys(1, 1, 1)
+            .syntax("key field")
+            .doc(
+                CommandGroup::Hash,
+                "2.0.0",
+                "O(1)",
+                "Returns the value of a field in a hash.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("hdel", -3, &[Write, Fast], handle_hdel_command)
+            .keys(1, 1, 1)
+            .syntax("key field [field ...]")
+            .doc(
+                CommandGroup::Hash,
+                "2.0.0",
+                "O(N) where N is the number of fields to be removed.",
+                "Deletes one or more fields and their values from a hash. Deletes the hash if no fields remain.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("hgetall", 2, &[ReadOnly], handle_hgetall_command)
+            .keys(1, 1, 1)
+            .syntax("key")
+            .doc(
+                CommandGroup::Hash,
+                "2.0.0",
+                "O(N) where N is the size of the hash.",
+                "Returns all fields and values in a hash.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("hscan", -3, &[ReadOnly], handle_hscan_command)
+            .keys(1, 1, 1)
+            .syntax("key cursor [MATCH pattern] [COUNT count]")
+            .doc(
+                CommandGroup::Hash,
+                "2.8.0",
+                "O(log N + COUNT) for every call. O(N) for a complete iteration.",
+                "Iterates over fields and values of a hash.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("hexists", 3, &[ReadOnly, Fast], handle_hexists_command)
+            .keys(1, 1, 1)
+            .syntax("key field")
+            .doc(
+                CommandGroup::Hash,
+                "2.0.0",
+                "O(1)",
+                "Determines whether a field exists in a hash.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("hlen", 2, &[ReadOnly, Fast], handle_hlen_command)
+            .keys(1, 1, 1)
+            .syntax("key")
+            .doc(
+                CommandGroup::Hash,
+                "2.0.0",
+                "O(1)",
+                "Returns the number of fields in a hash.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("hkeys", 2, &[ReadOnly], handle_hkeys_command)
+            .keys(1, 1, 1)
+            .syntax("key")
+            .doc(
+                CommandGroup::Hash,
+                "2.0.0",
+                "O(N) where N is the size of the hash.",
+                "Returns all fields in a hash.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("hvals", 2, &[ReadOnly], handle_hvals_command)
+            .keys(1, 1, 1)
+            .syntax("key")
+            .doc(
+                CommandGroup::Hash,
+                "2.0.0",
+                "O(N) where N is the size of the hash.",
+                "Returns all values in a hash.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("hmget", -3, &[ReadOnly, Fast], handle_hmget_command)
+            .keys(1, 1, 1)
+            .syntax("key field [field ...]")
+            .doc(
+                CommandGroup::Hash,
+                "2.0.0",
+                "O(N) where N is the number of fields being requested.",
+                "Returns the values of all fields in a hash.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("hsetnx", 4, &[Write, Fast], handle_hsetnx_command)
+            .keys(1, 1, 1)
+            .syntax("key field value")
+            .doc(
+                CommandGroup::Hash,
+                "2.0.0",
+                "O(1)",
+                "Sets the value of a field in a hash only when the field doesn't exist.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("hincrby", 4, &[Write, Fast], handle_hincrby_command)
+            .keys(1, 1, 1)
+            .syntax("key field increment")
+            .doc(
+                CommandGroup::Hash,
+                "2.0.0",
+                "O(1)",
+                "Increments the integer value of a field in a hash by a number. Uses 0 as initial value if the field doesn't exist.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("hincrbyfloat", 4, &[Write, Fast], handle_hincrbyfloat_command)
+            .keys(1, 1, 1)
+            .syntax("key field increment")
+            .doc(
+                CommandGroup::Hash,
+                "2.6.0",
+                "O(1)",
+                "Increments the floating point value of a field by a number. Uses 0 as initial value if the field doesn't exist.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("hstrlen", 3, &[ReadOnly, Fast], handle_hstrlen_command)
+            .keys(1, 1, 1)
+            .syntax("key field")
+            .doc(
+                CommandGroup::Hash,
+                "3.2.0",
+                "O(1)",
+                "Returns the length of the value of a field.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("hrandfield", -2, &[ReadOnly], handle_hrandfield_command)
+            .keys(1, 1, 1)
+            .syntax("key [count [WITHVALUES]]")
+            .doc(
+                CommandGroup::Hash,
+                "6.2.0",
+                "O(N) where N is the number of fields returned",
+                "Returns one or more random fields from a hash.",
+            ),
+    );
+}
+
+// 处理 HSET 命令，返回新增的field数量
+pub async fn handle_hset_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    // field 和 value 必须成对出现
+    if !parts.len().is_multiple_of(2) {
+        return wrong_args("hset");
+    }
+
+    let mut db = client.storage.db.lock().await;
+
+    // 确保键空间中存在该键对应的内部哈希表
+    let inner_map = match db.get_or_default::<HashValue>(&parts[1]) {
+        Ok(inner_map) => inner_map,
+        Err(err) => return err,
+    };
+
+    // 从第 2 个元素开始，每两个元素一组作为 field 和 value
+    let mut added = 0;
+    for pair in parts[2..].chunks(2) {
+        if inner_map.insert(pair[0].clone(), pair[1].clone()).is_none() {
+            added += 1;
         }
     }
+
+    Frame::Integer(added)
 }
 
-// 处理 HSET 命令
-pub async fn handle_hset_command(
-    parts: Vec<&str>,
-    socket: &mut tokio::net::TcpStream,
-    hash_table: Arc<Mutex<HashMap<String, HashMap<String, String>>>>,
-) {
-    if parts.len() <= 3 || parts.len() % 2 != 0 {
-        error!("命令格式不符合！");
-        socket
-            .write_all("命令格式不符合！".as_bytes())
-            .await
-            .unwrap();
-        return;
+// 处理 HGET 命令，直接在锁内读取单个field，不复制整个哈希表
+pub async fn handle_hget_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let db = client.storage.db.lock().await;
+    match db.get_as::<HashValue>(&parts[1]) {
+        Ok(inner_map) => match inner_map.and_then(|inner_map| inner_map.get(&parts[2])) {
+            Some(value) => Frame::Bulk(value.clone()),
+            None => Frame::Null,
+        },
+        Err(err) => err,
     }
+}
 
-    let key = parts[1].trim_end_matches('\0').to_string();
-
-    // 获取可变的哈希表锁
-    let mut map = hash_table.lock().await;
+// 处理 HDEL 命令，返回被删除的field数量
+pub async fn handle_hdel_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let key = &parts[1];
+    let mut db = client.storage.db.lock().await;
+    let inner_map = match db.get_mut_as::<HashValue>(key) {
+        Ok(Some(inner_map)) => inner_map,
+        Ok(None) => return Frame::Integer(0),
+        Err(err) => return err,
+    };
+    let removed = parts[2..]
+        .iter()
+        .filter(|field| inner_map.remove(field).is_some())
+        .count();
+    // 最后一个field被删除后，key也随之删除
+    if inner_map.is_empty() {
+        db.remove(key);
+    }
+    Frame::Integer(removed as i64)
+}
 
-    // 确保主哈希表中存在该键对应的内部哈希表
-    let inner_map = map.entry(key.clone()).or_insert(HashMap::new());
+// 处理 HGETALL 命令
+pub async fn handle_hgetall_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let db = client.storage.db.lock().await;
+    match db.get_as::<HashValue>(&parts[1]) {
+        Ok(Some(inner_map)) => Frame::Map(
+            inner_map
+                .iter()
+                .map(|(field, value)| (Frame::Bulk(field.clone()), Frame::Bulk(value.clone())))
+                .collect(),
+        ),
+        Ok(None) => Frame::Map(vec![]),
+        Err(err) => err,
+    }
+}
 
-    // 从第 2 个元素开始，每两个元素一组作为 field 和 value
-    for i in (2..parts.len()).step_by(2) {
-        let field = parts[i].trim_end_matches('\0').to_string();
-        let value = parts[i + 1].trim_end_matches('\0').to_string();
-        inner_map.insert(field, value);
-    }
-
-    socket.write_all("ok".as_bytes()).await.unwrap();
-}
-
-// 处理 HGET 命令
-pub async fn handle_hget_command(
-    parts: Vec<&str>,
-    socket: &mut tokio::net::TcpStream,
-    hash_table: Arc<Mutex<HashMap<String, HashMap<String, String>>>>,
-) {
-    if !check_command_format(&parts, 3) {
-        error!("命令格式不符合！");
-        socket
-            .write_all("命令格式不符合！".as_bytes())
-            .await
-            .unwrap();
-        return;
-    }
-
-    let field = parts[2].trim_end_matches('\0').to_string();
-    if let Some(inner_map) = get_inner_map(&parts, socket, hash_table.clone()).await {
-        if let Some(value) = inner_map.get(&field) {
-            socket.write_all(value.as_bytes()).await.unwrap();
-        } else {
-            error!("未找到field");
-            socket.write_all("未找到field".as_bytes()).await.unwrap();
+// 处理 HSCAN 命令，返回 [游标, [field, value, ...]]
+pub async fn handle_hscan_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let options = match ScanOptions::parse(&parts[2..], false) {
+        Ok(options) => options,
+        Err(err) => return err,
+    };
+    let db = client.storage.db.lock().await;
+    let inner_map = match db.get_as::<HashValue>(&parts[1]) {
+        Ok(Some(inner_map)) => inner_map,
+        Ok(None) => return scan_reply(0, vec![]),
+        Err(err) => return err,
+    };
+    let (cursor, fields) = inner_map.scan(options.cursor, options.count);
+    let mut res = Vec::new();
+    for field in fields {
+        if let Some(value) = inner_map.get(field)
+            && options.matches(field)
+        {
+            res.push(Frame::Bulk(field.clone()));
+            res.push(Frame::Bulk(value.clone()));
         }
     }
+    scan_reply(cursor, res)
+}
+
+// 处理 HEXISTS 命令
+pub async fn handle_hexists_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let db = client.storage.db.lock().await;
+    match db.get_as::<HashValue>(&parts[1]) {
+        Ok(inner_map) => Frame::Integer(
+            inner_map.is_some_and(|inner_map| inner_map.contains_key(&parts[2])) as i64,
+        ),
+        Err(err) => err,
+    }
+}
+
+// 处理 HLEN 命令
+pub async fn handle_hlen_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let db = client.storage.db.lock().await;
+    match db.get_as::<HashValue>(&parts[1]) {
+        Ok(inner_map) => Frame::Integer(inner_map.map_or(0, |inner_map| inner_map.len()) as i64),
+        Err(err) => err,
+    }
+}
+
+// 处理 HKEYS 命令
+pub async fn handle_hkeys_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let db = client.storage.db.lock().await;
+    match db.get_as::<HashValue>(&parts[1]) {
+        Ok(Some(inner_map)) => Frame::bulks(inner_map.keys().cloned()),
+        Ok(None) => Frame::Array(vec![]),
+        Err(err) => err,
+    }
 }
 
-// 处理 HDEL 命令
-pub async fn handle_hdel_command(
-    parts: Vec<&str>,
-    socket: &mut tokio::net::TcpStream,
-    hash_table: Arc<Mutex<HashMap<String, HashMap<String, String>>>>,
-) {
-    if !check_command_format(&parts, 3) {
-        error!("命令格式不符合！");
-        socket
-            .write_all("命令格式不符合！".as_bytes())
-            .await
-            .unwrap();
-        return;
-    }
-
-    let key = parts[1].trim_end_matches('\0').to_string();
-    let field = parts[2].trim_end_matches('\0').to_string();
-    let mut lock_hash = hash_table.lock().await;
-    if let Some(inner_map) = lock_hash.get_mut(&key) {
-        if let Some(value) = inner_map.remove(&field) {
-            socket.write_all(value.as_bytes()).await.unwrap();
+// 处理 HVALS 命令
+pub async fn handle_hvals_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let db = client.storage.db.lock().await;
+    match db.get_as::<HashValue>(&parts[1]) {
+        Ok(Some(inner_map)) => Frame::bulks(inner_map.values().cloned()),
+        Ok(None) => Frame::Array(vec![]),
+        Err(err) => err,
+    }
+}
+
+// 处理 HMGET 命令，不存在的field对应空值
+pub async fn handle_hmget_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let db = client.storage.db.lock().await;
+    let inner_map = match db.get_as::<HashValue>(&parts[1]) {
+        Ok(inner_map) => inner_map,
+        Err(err) => return err,
+    };
+    Frame::Array(
+        parts[2..]
+            .iter()
+            .map(
+                |field| match inner_map.and_then(|inner_map| inner_map.get(field)) {
+                    Some(value) => Frame::Bulk(value.clone()),
+                    None => Frame::Null,
+                },
+            )
+            .collect(),
+    )
+}
+
+// 处理 HSETNX 命令，field已存在时不修改，返回是否设置成功
+pub async fn handle_hsetnx_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let mut db = client.storage.db.lock().await;
+    let inner_map = match db.get_or_default::<HashValue>(&parts[1]) {
+        Ok(inner_map) => inner_map,
+        Err(err) => return err,
+    };
+    if inner_map.contains_key(&parts[2]) {
+        return Frame::Integer(0);
+    }
+    inner_map.insert(parts[2].clone(), parts[3].clone());
+    Frame::Integer(1)
+}
+
+// 处理 HINCRBY 命令，field不存在时视为 0
+pub async fn handle_hincrby_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let Some(increment) = parse_integer(&parts[3]) else {
+        return not_integer();
+    };
+    let mut db = client.storage.db.lock().await;
+    let inner_map = match db.get_or_default::<HashValue>(&parts[1]) {
+        Ok(inner_map) => inner_map,
+        Err(err) => return err,
+    };
+    let current = match inner_map.get(&parts[2]) {
+        Some(value) => match parse_integer(value) {
+            Some(current) => current,
+            None => return Frame::error("ERR hash value is not an integer"),
+        },
+        None => 0,
+    };
+    let Some(value) = current.checked_add(increment) else {
+        return Frame::error("ERR increment or decrement would overflow");
+    };
+    inner_map.replace(parts[2].clone(), value.to_string().into_bytes());
+    Frame::Integer(value)
+}
+
+// 处理 HINCRBYFLOAT 命令，返回并保存格式化后的结果
+pub async fn handle_hincrbyfloat_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let Some(increment) = parse_float(&parts[3]) else {
+        return Frame::error("ERR value is not a valid float");
+    };
+    if increment.is_infinite() {
+        return Frame::error("ERR value is NaN or Infinity");
+    }
+    let mut db = client.storage.db.lock().await;
+    let inner_map = match db.get_or_default::<HashValue>(&parts[1]) {
+        Ok(inner_map) => inner_map,
+        Err(err) => return err,
+    };
+    let current = match inner_map.get(&parts[2]) {
+        Some(value) => match parse_float(value) {
+            Some(current) => current,
+            None => return Frame::error("ERR hash value is not a float"),
+        },
+        None => 0.0,
+    };
+    let value = current + increment;
+    if !value.is_finite() {
+        return Frame::error("ERR increment would produce NaN or Infinity");
+    }
+    let value = format_float(value).into_bytes();
+    inner_map.replace(parts[2].clone(), value.clone());
+    Frame::Bulk(value)
+}
+
+// 处理 HSTRLEN 命令，field不存在时返回 0
+pub async fn handle_hstrlen_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let db = client.storage.db.lock().await;
+    match db.get_as::<HashValue>(&parts[1]) {
+        Ok(inner_map) => Frame::Integer(
+            inner_map
+                .and_then(|inner_map| inner_map.get(&parts[2]))
+                .map_or(0, |value| value.len()) as i64,
+        ),
+        Err(err) => err,
+    }
+}
+
+// 处理 HRANDFIELD 命令：HRANDFIELD key [count [WITHVALUES]]
+// count 为正数时返回不重复的field，为负数时允许重复，返回 |count| 个
+pub async fn handle_hrandfield_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let count = match parts.get(2) {
+        Some(count) => match parse_number::<i64>(count) {
+            // 与 Redis 一致，避免带 WITHVALUES 时回复长度溢出
+            Some(count) if count < -i64::MAX / 2 => {
+                return Frame::error("ERR value is out of range");
+            }
+            Some(count) => Some(count),
+            None => return not_integer(),
+        },
+        None => None,
+    };
+    let with_values = match parts.get(3) {
+        Some(option) if option.eq_ignore_ascii_case(b"withvalues") => true,
+        Some(_) => return Frame::error("ERR syntax error"),
+        None => false,
+    };
+    if parts.len() > 4 {
+        return Frame::error("ERR syntax error");
+    }
+
+    let db = client.storage.db.lock().await;
+    let inner_map = match db.get_as::<HashValue>(&parts[1]) {
+        Ok(inner_map) => inner_map,
+        Err(err) => return err,
+    };
+    let mut rng = rand::thread_rng();
+    let Some(count) = count else {
+        return match inner_map.and_then(|inner_map| inner_map.keys().choose(&mut rng)) {
+            Some(field) => Frame::Bulk(field.clone()),
+            None => Frame::Null,
+        };
+    };
+    let Some(inner_map) = inner_map else {
+        return Frame::Array(vec![]);
+    };
+
+    let picked: Vec<(&Vec<u8>, &Vec<u8>)> = if count >= 0 {
+        inner_map.iter().choose_multiple(&mut rng, count as usize)
+    } else {
+        let entries: Vec<_> = inner_map.iter().collect();
+        (0..count.unsigned_abs())
+            .filter_map(|_| entries.choose(&mut rng).copied())
+            .collect()
+    };
+    if !with_values {
+        return Frame::bulks(picked.into_iter().map(|(field, _)| field.clone()));
+    }
+    // RESP3 下每个field和value组成一个二元数组，RESP2 下平铺
+    let mut res = Vec::new();
+    for (field, value) in picked {
+        let (field, value) = (Frame::Bulk(field.clone()), Frame::Bulk(value.clone()));
+        if client.protocol == Protocol::Resp3 {
+            res.push(Frame::Array(vec![field, value]));
         } else {
-            error!("未找到field");
-            socket.write_all("未找到field".as_bytes()).await.unwrap();
+            res.push(field);
+            res.push(value);
         }
-    } else {
-        error!("未找到key");
-        socket.write_all("未找到key".as_bytes()).await.unwrap();
     }
+    Frame::Array(res)
 }
 
-// 处理 HGETALL 命令
-pub async fn handle_hgetall_command(
-    parts: Vec<&str>,
-    socket: &mut tokio::net::TcpStream,
-    hash_table: Arc<Mutex<HashMap<String, HashMap<String, String>>>>,
-) {
-    if !check_command_format(&parts, 2) {
-        error!("命令格式不符合！");
-        socket
-            .write_all("命令格式不符合！".as_bytes())
-            .await
-            .unwrap();
-        return;
-    }
-
-    if let Some(inner_map) = get_inner_map(&parts, socket, hash_table.clone()).await {
-        let mut res = String::new();
-        for (field, value) in inner_map.iter() {
-            let message = format!("{}:{}\n", field, value);
-            res.push_str(&message);
-        }
-        socket.write_all(res.as_bytes()).await.unwrap();
+#[cfg(test)]
+mod tests {
+    use crate::operations::testing::{bulk, client, run};
+    use crate::protocol::frame::Frame;
+
+    #[tokio::test]
+    async fn hincrbyfloat_keeps_full_precision() {
+        let mut client = client();
+        run(&mut client, &["hset", "h", "f", "1234567890123456"]).await;
+        assert_eq!(
+            run(&mut client, &["hincrbyfloat", "h", "f", "1"]).await,
+            bulk("1234567890123457")
+        );
+        assert_eq!(
+            run(&mut client, &["hget", "h", "f"]).await,
+            bulk("1234567890123457")
+        );
+        assert_eq!(
+            run(&mut client, &["hincrbyfloat", "h", "g", "10.5"]).await,
+            bulk("10.5")
+        );
+        assert_eq!(
+            run(&mut client, &["hincrbyfloat", "h", "g", "0.1"]).await,
+            bulk("10.6")
+        );
+        assert_eq!(
+            run(&mut client, &["hincrbyfloat", "h", "g", "nan"]).await,
+            Frame::error("ERR value is not a valid float")
+        );
+        run(&mut client, &["hset", "h", "s", "abc"]).await;
+        assert_eq!(
+            run(&mut client, &["hincrbyfloat", "h", "s", "1"]).await,
+            Frame::error("ERR hash value is not a float")
+        );
     }
 }
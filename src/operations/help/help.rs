@@ -1,6 +1,108 @@
-use tokio::io::AsyncWriteExt;
+use crate::command::spec::{CommandGroup, CommandSpec};
+use crate::command::table::{COMMAND_TABLE, CommandTable};
+use crate::connection::client::Client;
+use crate::operations::utils::wrong_args;
+use crate::protocol::frame::Frame;
 
-pub async fn help(socket: &mut tokio::net::TcpStream) {
-    let res = "支持命令：SET、GET、HSET、HGET、LPUSH等各类数据操作";
-    socket.write_all(res.as_bytes()).await.unwrap();
-}
\ No newline at end of file
+// 注册 help 命令
+pub fn register(table: &mut CommandTable) {
+    table.register(
+        CommandSpec::new("help", -1, &[], help)
+            .syntax("[command]")
+            .doc(
+                CommandGroup::Server,
+                "1.0.0",
+                "O(1)",
+                "Lists the commands supported by the server.",
+            ),
+    );
+}
+
+// 处理 HELP 命令：不带参数时按分组列出所有命令，带命令名时给出该命令的详细说明
+// 输出全部由命令表中的元数据生成，新注册的命令会自动出现
+pub async fn help(parts: Vec<Vec<u8>>, _client: &mut Client) -> Frame {
+    match parts.len() {
+        1 => lines(command_list()),
+        2 => match COMMAND_TABLE.lookup(&parts[1]) {
+            Some(spec) => lines(command_detail(spec)),
+            None => Frame::error(format!(
+                "ERR unknown command '{}'",
+                String::from_utf8_lossy(&parts[1])
+            )),
+        },
+        _ => wrong_args("help"),
+    }
+}
+
+// 生成 `<命令> HELP` 的回复，列出该命令的所有子命令
+pub fn subcommand_help(spec: &CommandSpec) -> Frame {
+    let name = spec.name.to_uppercase();
+    let mut out = vec![format!(
+        "{} <subcommand> [<arg> [value] [opt] ...]. Subcommands are:",
+        name
+    )];
+    for sub in &spec.subcommands {
+        out.push(subcommand_usage(sub.name, sub.syntax));
+        out.push(format!("    {}", sub.summary));
+    }
+    out.push("HELP".to_string());
+    out.push("    Prints this help.".to_string());
+    lines(out)
+}
+
+// 所有命令按分组排列，组内按命令名排序
+fn command_list() -> Vec<String> {
+    let mut out = vec!["Commands are:".to_string()];
+    for group in CommandGroup::ALL {
+        let mut specs: Vec<_> = COMMAND_TABLE
+            .iter()
+            .filter(|spec| spec.group == group)
+            .collect();
+        if specs.is_empty() {
+            continue;
+        }
+        specs.sort_by_key(|spec| spec.name);
+        out.push(format!("{}:", group.name()));
+        for spec in specs {
+            out.push(format!("    {}", spec.usage()));
+            out.push(format!("        {}", spec.summary));
+        }
+    }
+    out.push("Use HELP <command> for details about a command.".to_string());
+    out
+}
+
+fn command_detail(spec: &CommandSpec) -> Vec<String> {
+    let mut out = vec![
+        spec.usage(),
+        format!("summary: {}", spec.summary),
+        format!("complexity: {}", spec.complexity),
+        format!("since: {}", spec.since),
+        format!("group: {}", spec.group.name()),
+    ];
+    if !spec.subcommands.is_empty() {
+        out.push("subcommands:".to_string());
+        for sub in &spec.subcommands {
+            out.push(format!(
+                "    {} {}",
+                spec.name.to_uppercase(),
+                subcommand_usage(sub.name, sub.syntax)
+            ));
+            out.push(format!("        {}", sub.summary));
+        }
+    }
+    out
+}
+
+fn subcommand_usage(name: &str, syntax: &str) -> String {
+    let name = name.to_uppercase();
+    if syntax.is_empty() {
+        name
+    } else {
+        format!("{} {}", name, syntax)
+    }
+}
+
+fn lines(lines: Vec<String>) -> Frame {
+    Frame::Array(lines.into_iter().map(Frame::Simple).collect())
+}
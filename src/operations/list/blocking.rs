@@ -0,0 +1,214 @@
+use crate::command::spec::CommandFlag::{Blocking, Write};
+use crate::command::spec::{CommandGroup, CommandSpec, numkeys_positions};
+use crate::command::table::CommandTable;
+use crate::connection::client::Client;
+use crate::db::blocking::{ServeFn, Waiter};
+use crate::db::storage::Storage;
+use crate::operations::list::ops::{ListEnd, MpopArgs, move_element, pop_elements};
+use crate::operations::utils::parse_number;
+use crate::protocol::frame::Frame;
+use std::sync::Arc;
+use std::time::Duration;
+
+// 注册阻塞的 list 命令
+pub fn register(table: &mut CommandTable) {
+    table.register(
+        CommandSpec::new("blpop", -3, &[Write, Blocking], handle_blpop_command)
+            .keys(1, -2, 1)
+            .syntax("key [key ...] timeout")
+            .doc(
+                CommandGroup::List,
+                "2.0.0",
+                "O(N) where N is the number of provided keys.",
+                "Removes and returns the first element in a list. Blocks until an element is available otherwise. Deletes the list if the last element was popped.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("brpop", -3, &[Write, Blocking], handle_brpop_command)
+            .keys(1, -2, 1)
+            .syntax("key [key ...] timeout")
+            .doc(
+                CommandGroup::List,
+                "2.0.0",
+                "O(N) where N is the number of provided keys.",
+                "Removes and returns the last element in a list. Blocks until an element is available otherwise. Deletes the list if the last element was popped.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("blmove", 6, &[Write, Blocking], handle_blmove_command)
+            .keys(1, 2, 1)
+            .syntax("source destination <LEFT | RIGHT> <LEFT | RIGHT> timeout")
+            .doc(
+                CommandGroup::List,
+                "6.2.0",
+                "O(1)",
+                "Pops an element from a list, pushes it to another list and returns it. Blocks until an element is available otherwise. Deletes the list if the last element was moved.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("blmpop", -5, &[Write, Blocking], handle_blmpop_command)
+            .movable_keys(numkeys_positions::<2>)
+            .syntax("timeout numkeys key [key ...] <LEFT | RIGHT> [COUNT count]")
+            .doc(
+                CommandGroup::List,
+                "7.0.0",
+                "O(N+M) where N is the number of provided keys and M is the number of elements returned.",
+                "Pops the first element from one of multiple lists. Blocks until an element is available otherwise. Deletes the list if the last element was popped.",
+            ),
+    );
+}
+
+// 解析以秒为单位的超时时间，可以带小数，0 表示一直阻塞
+fn parse_timeout(arg: &[u8]) -> Result<Option<Duration>, Frame> {
+    let Some(timeout) = parse_number::<f64>(arg).filter(|timeout| timeout.is_finite()) else {
+        return Err(Frame::error("ERR timeout is not a float or out of range"));
+    };
+    if timeout < 0.0 {
+        return Err(Frame::error("ERR timeout is negative"));
+    }
+    if timeout == 0.0 {
+        return Ok(None);
+    }
+    match Duration::try_from_secs_f64(timeout) {
+        Ok(timeout) => Ok(Some(timeout)),
+        Err(_) => Err(Frame::error("ERR timeout is out of range")),
+    }
+}
+
+// 阻塞命令的公共实现：先按顺序在 keys 上尝试 serve，都没有元素时阻塞，
+// 等其他客户端写入元素后由写入方按阻塞的先后顺序调用 serve，超时与 Redis 一样返回空数组
+async fn block_generic(
+    client: &mut Client,
+    keys: &[Vec<u8>],
+    timeout: Option<Duration>,
+    serve: ServeFn,
+) -> Frame {
+    let waiter = {
+        let mut db = client.storage.db.lock().await;
+        for key in keys {
+            if let Some(reply) = serve(&mut db, key) {
+                return reply;
+            }
+        }
+        let waiter = Waiter::new(serve);
+        db.block(keys, &waiter);
+        waiter
+    };
+    let _guard = UnblockOnDrop {
+        storage: client.storage.clone(),
+        keys: keys.to_vec(),
+        waiter: waiter.clone(),
+    };
+    waiter.wait(timeout).await.unwrap_or(Frame::NullArray)
+}
+
+// 等待结束后把 waiter 从所有 key 的等待队列中移除，
+// 客户端断开时等待的 future 直接被丢弃，因此放在 drop 中处理
+struct UnblockOnDrop {
+    storage: Storage,
+    keys: Vec<Vec<u8>>,
+    waiter: Arc<Waiter>,
+}
+
+impl Drop for UnblockOnDrop {
+    fn drop(&mut self) {
+        // drop 中不能等待锁，锁被占用时交给后台任务处理
+        if let Ok(mut db) = self.storage.db.try_lock() {
+            db.unblock(&self.keys, &self.waiter);
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let storage = self.storage.clone();
+        let keys = std::mem::take(&mut self.keys);
+        let waiter = self.waiter.clone();
+        runtime.spawn(async move {
+            storage.db.lock().await.unblock(&keys, &waiter);
+        });
+    }
+}
+
+// BLPOP、BRPOP 的公共实现，返回 [key, 元素]
+async fn bpop_generic(parts: &[Vec<u8>], client: &mut Client, end: ListEnd) -> Frame {
+    let timeout = match parse_timeout(&parts[parts.len() - 1]) {
+        Ok(timeout) => timeout,
+        Err(err) => return err,
+    };
+    let serve: ServeFn = Box::new(move |db, key| match pop_elements(db, key, end, 1) {
+        Ok(Some(mut values)) => values
+            .pop()
+            .map(|value| Frame::Array(vec![Frame::Bulk(key.to_vec()), Frame::Bulk(value)])),
+        Ok(None) => None,
+        Err(err) => Some(err),
+    });
+    block_generic(client, &parts[1..parts.len() - 1], timeout, serve).await
+}
+
+// 处理 BLPOP 命令
+pub async fn handle_blpop_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    bpop_generic(&parts, client, ListEnd::Left).await
+}
+
+// 处理 BRPOP 命令
+pub async fn handle_brpop_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    bpop_generic(&parts, client, ListEnd::Right).await
+}
+
+// 处理 BLMOVE 命令，返回被移动的元素
+pub async fn handle_blmove_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let (Some(from), Some(to)) = (ListEnd::parse(&parts[3]), ListEnd::parse(&parts[4])) else {
+        return Frame::error("ERR syntax error");
+    };
+    let timeout = match parse_timeout(&parts[5]) {
+        Ok(timeout) => timeout,
+        Err(err) => return err,
+    };
+    let destination = parts[2].clone();
+    let serve: ServeFn = Box::new(move |db, source| {
+        let moved = move_element(db, source, &destination, from, to);
+        moved.map_or_else(Some, |value| value.map(Frame::Bulk))
+    });
+    block_generic(client, &parts[1..2], timeout, serve).await
+}
+
+// 处理 BLMPOP 命令，从第一个非空的列表中弹出最多 count 个元素，返回 [key, [元素, ...]]
+pub async fn handle_blmpop_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let timeout = match parse_timeout(&parts[1]) {
+        Ok(timeout) => timeout,
+        Err(err) => return err,
+    };
+    let MpopArgs { keys, end, count } = match MpopArgs::parse(&parts[2..]) {
+        Ok(args) => args,
+        Err(err) => return err,
+    };
+    let serve: ServeFn = Box::new(move |db, key| match pop_elements(db, key, end, count) {
+        Ok(Some(values)) => Some(Frame::Array(vec![
+            Frame::Bulk(key.to_vec()),
+            Frame::bulks(values),
+        ])),
+        Ok(None) => None,
+        Err(err) => Some(err),
+    });
+    block_generic(client, &keys, timeout, serve).await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::operations::testing::{client, run};
+    use crate::protocol::frame::Frame;
+
+    #[tokio::test]
+    async fn timeouts_reply_with_null_array() {
+        let mut client = client();
+        let commands: [&[&str]; 4] = [
+            &["blpop", "a", "b", "0.01"],
+            &["brpop", "a", "0.01"],
+            &["blmove", "a", "b", "left", "right", "0.01"],
+            &["blmpop", "0.01", "2", "a", "b", "left"],
+        ];
+        for command in commands {
+            assert_eq!(run(&mut client, command).await, Frame::NullArray);
+        }
+    }
+}
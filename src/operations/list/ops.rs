@@ -1,173 +1,889 @@
-use log::error;
-use std::collections::{HashMap, VecDeque};
-use std::sync::Arc;
-use tokio::io::AsyncWriteExt;
-use tokio::sync::Mutex;
-
-// 处理 LPUSH 命令，从左侧添加元素
-pub async fn handle_lpush_command(
-    parts: Vec<&str>,
-    socket: &mut tokio::net::TcpStream,
-    hash_table: Arc<Mutex<HashMap<String, VecDeque<String>>>>,
-) {
-    if parts.len() < 3 {
-        error!("命令格式不符合！");
-        socket
-            .write_all("命令格式不符合！".as_bytes())
-            .await
-            .unwrap();
-        return;
-    }
-    let key = parts[1].trim_end_matches('\0').to_string();
-    let mut lock_hash = hash_table.lock().await;
-    if let Some(inner_list) = lock_hash.get_mut(&key) {
-        // 如果存在直接追加
-        for i in 2..parts.len() {
-            inner_list.push_front(parts[i].trim_end_matches('\0').to_string());
+use crate::command::spec::CommandFlag::{Fast, ReadOnly, Write};
+use crate::command::spec::{CommandGroup, CommandSpec, numkeys_positions};
+use crate::command::table::CommandTable;
+use crate::connection::client::Client;
+use crate::db::storage::Db;
+use crate::db::value::ListValue;
+use crate::operations::utils::{normalize_range, not_integer, parse_number, wrong_args};
+use crate::protocol::frame::Frame;
+
+// 注册 list 类型的命令
+pub fn register(table: &mut CommandTable) {
+    table.register(
+        CommandSpec::new("lpush", -3, &[Write, Fast], handle_lpush_command)
+            .keys(1, 1, 1)
+            .syntax("key element [element ...]")
+            .doc(
+                CommandGroup::List,
+                "1.0.0",
+                "O(1) for each element added, so O(N) to add N elements when the command is called with multiple arguments.",
+                "Prepends one or more elements to a list. Creates the key if it doesn't exist.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("rpush", -3, &[Write, Fast], handle_rpush_command)
+            .keys(1, 1, 1)
+            .syntax("key element [element ...]")
+            .doc(
+                CommandGroup::List,
+                "1.0.0",
+                "O(1) for each element added, so O(N) to add N elements when the command is called with multiple arguments.",
+                "Appends one or more elements to a list. Creates the key if it doesn't exist.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("lpushx", -3, &[Write, Fast], handle_lpushx_command)
+            .keys(1, 1, 1)
+            .syntax("key element [element ...]")
+            .doc(
+                CommandGroup::List,
+                "2.2.0",
+                "O(1) for each element added, so O(N) to add N elements when the command is called with multiple arguments.",
+                "Prepends one or more elements to a list only when the list exists.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("rpushx", -3, &[Write, Fast], handle_rpushx_command)
+            .keys(1, 1, 1)
+            .syntax("key element [element ...]")
+            .doc(
+                CommandGroup::List,
+                "2.2.0",
+                "O(1) for each element added, so O(N) to add N elements when the command is called with multiple arguments.",
+                "Appends an element to a list only when the list exists.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("lpop", -2, &[Write, Fast], handle_lpop_command)
+            .keys(1, 1, 1)
+            .syntax("key [count]")
+            .doc(
+                CommandGroup::List,
+                "1.0.0",
+                "O(N) where N is the number of elements returned",
+                "Returns the first elements in a list after removing it. Deletes the list if the last element was popped.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("rpop", -2, &[Write, Fast], handle_rpop_command)
+            .keys(1, 1, 1)
+            .syntax("key [count]")
+            .doc(
+                CommandGroup::List,
+                "1.0.0",
+                "O(N) where N is the number of elements returned",
+                "Returns and removes the last elements of a list. Deletes the list if the last element was popped.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("lrange", 4, &[ReadOnly], handle_lrange_command)
+            .keys(1, 1, 1)
+            .syntax("key start stop")
+            .doc(
+                CommandGroup::List,
+                "1.0.0",
+                "O(S+N) where S is the distance of start offset from HEAD for small lists, from nearest end (HEAD or TAIL) for large lists; and N is the number of elements in the specified range.",
+                "Returns a range of elements from a list.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("llen", 2, &[ReadOnly, Fast], handle_llen_command)
+            .keys(1, 1, 1)
+            .syntax("key")
+            .doc(
+                CommandGroup::List,
+                "1.0.0",
+                "O(1)",
+                "Returns the length of a list.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("lindex", 3, &[ReadOnly], handle_lindex_command)
+            .keys(1, 1, 1)
+            .syntax("key index")
+            .doc(
+                CommandGroup::List,
+                "1.0.0",
+                "O(N) where N is the number of elements to traverse to get to the element at index. This makes asking for the first or the last element of the list O(1).",
+                "Returns an element from a list by its index.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("lset", 4, &[Write], handle_lset_command)
+            .keys(1, 1, 1)
+            .syntax("key index element")
+            .doc(
+                CommandGroup::List,
+                "1.0.0",
+                "O(N) where N is the length of the list. Setting either the first or the last element of the list is O(1).",
+                "Sets the value of an element in a list by its index.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("linsert", 5, &[Write], handle_linsert_command)
+            .keys(1, 1, 1)
+            .syntax("key <BEFORE | AFTER> pivot element")
+            .doc(
+                CommandGroup::List,
+                "2.2.0",
+                "O(N) where N is the number of elements to traverse before seeing the value pivot. This means that inserting somewhere on the left end on the list (head) can be considered O(1) and inserting somewhere on the right end (tail) is O(N).",
+                "Inserts an element before or after another element in a list.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("lrem", 4, &[Write], handle_lrem_command)
+            .keys(1, 1, 1)
+            .syntax("key count element")
+            .doc(
+                CommandGroup::List,
+                "1.0.0",
+                "O(N+M) where N is the length of the list and M is the number of elements removed.",
+                "Removes elements from a list. Deletes the list if the last element was removed.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("ltrim", 4, &[Write], handle_ltrim_command)
+            .keys(1, 1, 1)
+            .syntax("key start stop")
+            .doc(
+                CommandGroup::List,
+                "1.0.0",
+                "O(N) where N is the number of elements to be removed by the operation.",
+                "Removes elements from both ends a list. Deletes the list if all elements were trimmed.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("lpos", -3, &[ReadOnly], handle_lpos_command)
+            .keys(1, 1, 1)
+            .syntax("key element [RANK rank] [COUNT num-matches] [MAXLEN len]")
+            .doc(
+                CommandGroup::List,
+                "6.0.6",
+                "O(N) where N is the number of elements in the list, for the average case. When searching for elements near the head or the tail of the list, or when the MAXLEN option is provided, the command may run in constant time.",
+                "Returns the index of matching elements in a list.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("lmove", 5, &[Write], handle_lmove_command)
+            .keys(1, 2, 1)
+            .syntax("source destination <LEFT | RIGHT> <LEFT | RIGHT>")
+            .doc(
+                CommandGroup::List,
+                "6.2.0",
+                "O(1)",
+                "Returns an element after popping it from one list and pushing it to another. Deletes the list if the last element was moved.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("rpoplpush", 3, &[Write], handle_rpoplpush_command)
+            .keys(1, 2, 1)
+            .syntax("source destination")
+            .doc(
+                CommandGroup::List,
+                "1.2.0",
+                "O(1)",
+                "Returns the last element of a list after removing and pushing it to another list. Deletes the list if the last element was popped.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("lmpop", -4, &[Write], handle_lmpop_command)
+            .movable_keys(numkeys_positions::<1>)
+            .syntax("numkeys key [key ...] <LEFT | RIGHT> [COUNT count]")
+            .doc(
+                CommandGroup::List,
+                "7.0.0",
+                "O(N+M) where N is the number of provided keys and M is the number of elements returned.",
+                "Returns multiple elements from a list after removing them. Deletes the list if the last element was popped.",
+            ),
+    );
+}
+
+// 处理 LPUSH 命令，从左侧添加元素，返回列表长度
+pub async fn handle_lpush_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let mut db = client.storage.db.lock().await;
+    // 如果不存在创建一个新的
+    let inner_list = match db.get_or_default::<ListValue>(&parts[1]) {
+        Ok(inner_list) => inner_list,
+        Err(err) => return err,
+    };
+    for part in &parts[2..] {
+        inner_list.push_front(part.clone());
+    }
+    let len = inner_list.len();
+    db.wake_blocked(&parts[1]);
+    Frame::Integer(len as i64)
+}
+
+// 处理 RPUSH 命令，从右侧添加元素，返回列表长度
+pub async fn handle_rpush_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let mut db = client.storage.db.lock().await;
+    // 如果不存在创建一个新的
+    let inner_list = match db.get_or_default::<ListValue>(&parts[1]) {
+        Ok(inner_list) => inner_list,
+        Err(err) => return err,
+    };
+    for part in &parts[2..] {
+        inner_list.push_back(part.clone());
+    }
+    let len = inner_list.len();
+    db.wake_blocked(&parts[1]);
+    Frame::Integer(len as i64)
+}
+
+// LPUSHX、RPUSHX 的公共实现，列表不存在时不做任何操作，返回列表长度
+async fn pushx_generic(parts: &[Vec<u8>], client: &mut Client, front: bool) -> Frame {
+    let mut db = client.storage.db.lock().await;
+    let inner_list = match db.get_mut_as::<ListValue>(&parts[1]) {
+        Ok(Some(inner_list)) => inner_list,
+        Ok(None) => return Frame::Integer(0),
+        Err(err) => return err,
+    };
+    for part in &parts[2..] {
+        if front {
+            inner_list.push_front(part.clone());
+        } else {
+            inner_list.push_back(part.clone());
         }
-    } else {
-        // 如果不存在创建一个新的
-        lock_hash.insert(key.clone(), VecDeque::new());
-        for i in 2..parts.len() {
-            lock_hash
-                .get_mut(&key)
-                .unwrap()
-                .push_front(parts[i].trim_end_matches('\0').to_string());
+    }
+    Frame::Integer(inner_list.len() as i64)
+}
+
+// 处理 LPUSHX 命令
+pub async fn handle_lpushx_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    pushx_generic(&parts, client, true).await
+}
+
+// 处理 RPUSHX 命令
+pub async fn handle_rpushx_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    pushx_generic(&parts, client, false).await
+}
+
+// LPOP、RPOP 的公共实现：[count] 省略时返回单个元素，否则返回最多 count 个元素的数组
+async fn pop_generic(parts: &[Vec<u8>], client: &mut Client, command: &str, end: ListEnd) -> Frame {
+    if parts.len() > 3 {
+        return wrong_args(command);
+    }
+    let count = match parts.get(2) {
+        Some(count) => match parse_number::<i64>(count) {
+            Some(count) if count >= 0 => Some(count as usize),
+            Some(_) => return Frame::error("ERR value is out of range, must be positive"),
+            None => return not_integer(),
+        },
+        None => None,
+    };
+
+    let mut db = client.storage.db.lock().await;
+    let mut values = match pop_elements(&mut db, &parts[1], end, count.unwrap_or(1)) {
+        Ok(Some(values)) => values,
+        Ok(None) => return Frame::Null,
+        Err(err) => return err,
+    };
+    match count {
+        Some(_) => Frame::bulks(values),
+        None => match values.pop() {
+            Some(value) => Frame::Bulk(value),
+            None => Frame::Null,
+        },
+    }
+}
+
+// 处理 LPOP 命令，从左侧弹出元素
+pub async fn handle_lpop_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    pop_generic(&parts, client, "lpop", ListEnd::Left).await
+}
+
+// 处理 RPOP 命令，从右侧弹出元素
+pub async fn handle_rpop_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    pop_generic(&parts, client, "rpop", ListEnd::Right).await
+}
+
+// 处理 LRANGE 命令，返回 [start, stop] 范围内的元素，负数下标从尾部开始计算
+pub async fn handle_lrange_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let (Some(start), Some(stop)) = (
+        parse_number::<i64>(&parts[2]),
+        parse_number::<i64>(&parts[3]),
+    ) else {
+        return not_integer();
+    };
+
+    let db = client.storage.db.lock().await;
+    let inner_list = match db.get_as::<ListValue>(&parts[1]) {
+        Ok(Some(inner_list)) => inner_list,
+        Ok(None) => return Frame::Array(vec![]),
+        Err(err) => return err,
+    };
+    match normalize_range(start, stop, inner_list.len()) {
+        Some((start, stop)) => Frame::bulks(inner_list.range(start..=stop).cloned()),
+        None => Frame::Array(vec![]),
+    }
+}
+
+// 处理 LLEN 命令，key 不存在时返回 0
+pub async fn handle_llen_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let db = client.storage.db.lock().await;
+    match db.get_as::<ListValue>(&parts[1]) {
+        Ok(inner_list) => {
+            Frame::Integer(inner_list.map_or(0, |inner_list| inner_list.len()) as i64)
         }
+        Err(err) => err,
+    }
+}
+
+// 将可能为负数的下标转换为从头开始的下标，超出范围时返回 None
+fn list_index(index: i64, len: usize) -> Option<usize> {
+    let index = if index < 0 { index + len as i64 } else { index };
+    (0..len as i64).contains(&index).then_some(index as usize)
+}
+
+// 处理 LINDEX 命令，负数下标从尾部开始计算，-1 表示最后一个元素
+pub async fn handle_lindex_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let Some(index) = parse_number::<i64>(&parts[2]) else {
+        return not_integer();
+    };
+    let db = client.storage.db.lock().await;
+    let inner_list = match db.get_as::<ListValue>(&parts[1]) {
+        Ok(Some(inner_list)) => inner_list,
+        Ok(None) => return Frame::Null,
+        Err(err) => return err,
+    };
+    match list_index(index, inner_list.len()) {
+        Some(index) => Frame::Bulk(inner_list[index].clone()),
+        None => Frame::Null,
     }
-    socket.write_all("ok".as_bytes()).await.unwrap();
-}
-
-// 处理 RPUSH 命令，从右侧添加元素
-pub async fn handle_rpush_command(
-    parts: Vec<&str>,
-    socket: &mut tokio::net::TcpStream,
-    hash_table: Arc<Mutex<HashMap<String, VecDeque<String>>>>,
-) {
-    if parts.len() < 3 {
-        error!("命令格式不符合！");
-        socket
-            .write_all("命令格式不符合！".as_bytes())
-            .await
-            .unwrap();
-        return;
-    }
-    let key = parts[1].trim_end_matches('\0').to_string();
-    let mut lock_hash = hash_table.lock().await;
-    if let Some(inner_list) = lock_hash.get_mut(&key) {
-        // 如果存在直接追加
-        for i in 2..parts.len() {
-            inner_list.push_back(parts[i].trim_end_matches('\0').to_string());
+}
+
+// 处理 LSET 命令，key 不存在或下标越界时返回错误
+pub async fn handle_lset_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let Some(index) = parse_number::<i64>(&parts[2]) else {
+        return not_integer();
+    };
+    let mut db = client.storage.db.lock().await;
+    let inner_list = match db.get_mut_as::<ListValue>(&parts[1]) {
+        Ok(Some(inner_list)) => inner_list,
+        Ok(None) => return Frame::error("ERR no such key"),
+        Err(err) => return err,
+    };
+    match list_index(index, inner_list.len()) {
+        Some(index) => {
+            inner_list[index] = parts[3].clone();
+            Frame::ok()
         }
+        None => Frame::error("ERR index out of range"),
+    }
+}
+
+// 处理 LINSERT 命令，在第一个等于 pivot 的元素前或后插入
+// 返回插入后的列表长度，key 不存在返回 0，找不到 pivot 返回 -1
+pub async fn handle_linsert_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let after = if parts[2].eq_ignore_ascii_case(b"after") {
+        true
+    } else if parts[2].eq_ignore_ascii_case(b"before") {
+        false
     } else {
-        // 如果不存在创建一个新的
-        lock_hash.insert(key.clone(), VecDeque::new());
-        for i in 2..parts.len() {
-            lock_hash
-                .get_mut(&key)
-                .unwrap()
-                .push_back(parts[i].trim_end_matches('\0').to_string());
+        return Frame::error("ERR syntax error");
+    };
+    let mut db = client.storage.db.lock().await;
+    let inner_list = match db.get_mut_as::<ListValue>(&parts[1]) {
+        Ok(Some(inner_list)) => inner_list,
+        Ok(None) => return Frame::Integer(0),
+        Err(err) => return err,
+    };
+    let Some(position) = inner_list.iter().position(|value| *value == parts[3]) else {
+        return Frame::Integer(-1);
+    };
+    inner_list.insert(position + after as usize, parts[4].clone());
+    Frame::Integer(inner_list.len() as i64)
+}
+
+// 处理 LREM 命令，count 大于 0 时从头部开始删除 count 个，小于 0 时从尾部开始删除 |count| 个，
+// 等于 0 时删除所有等于 element 的元素，返回删除的数量
+pub async fn handle_lrem_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let Some(count) = parse_number::<i64>(&parts[2]) else {
+        return not_integer();
+    };
+    let key = &parts[1];
+    let element = &parts[3];
+    let mut db = client.storage.db.lock().await;
+    let inner_list = match db.get_mut_as::<ListValue>(key) {
+        Ok(Some(inner_list)) => inner_list,
+        Ok(None) => return Frame::Integer(0),
+        Err(err) => return err,
+    };
+    let matches = inner_list.iter().filter(|value| *value == element).count();
+    let limit = (count.unsigned_abs() as usize).min(matches);
+    // 删除第 first 个之后的 removed 个匹配的元素
+    let (first, removed) = match count {
+        0 => (0, matches),
+        count if count > 0 => (0, limit),
+        _ => (matches - limit, limit),
+    };
+    let mut seen = 0;
+    inner_list.retain(|value| {
+        if value != element {
+            return true;
         }
+        seen += 1;
+        seen <= first || seen > first + removed
+    });
+    // 列表为空时删除key
+    if inner_list.is_empty() {
+        db.remove(key);
     }
-    socket.write_all("ok".as_bytes()).await.unwrap();
-}
-
-// 处理 LPOP 命令，从左侧添加元素
-pub async fn handle_lpop_command(
-    parts: Vec<&str>,
-    socket: &mut tokio::net::TcpStream,
-    hash_table: Arc<Mutex<HashMap<String, VecDeque<String>>>>,
-) {
-    if parts.len() != 2 {
-        error!("命令格式不符合！");
-        socket
-            .write_all("命令格式不符合！".as_bytes())
-            .await
-            .unwrap();
-        return;
-    }
-    let key = parts[1].trim_end_matches('\0').to_string();
-    let mut lock_hash = hash_table.lock().await;
-    if let Some(inner_list) = lock_hash.get_mut(&key) {
-        if let Some(value) = inner_list.pop_front() {
-            socket.write_all(value.as_bytes()).await.unwrap();
-        } else {
-            error!("元素不存在");
-            socket.write_all("元素不存在".as_bytes()).await.unwrap();
+    Frame::Integer(removed as i64)
+}
+
+// 处理 LTRIM 命令，只保留 [start, stop] 范围内的元素，范围为空时删除key
+pub async fn handle_ltrim_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let (Some(start), Some(stop)) = (
+        parse_number::<i64>(&parts[2]),
+        parse_number::<i64>(&parts[3]),
+    ) else {
+        return not_integer();
+    };
+    let key = &parts[1];
+    let mut db = client.storage.db.lock().await;
+    let inner_list = match db.get_mut_as::<ListValue>(key) {
+        Ok(Some(inner_list)) => inner_list,
+        Ok(None) => return Frame::ok(),
+        Err(err) => return err,
+    };
+    match normalize_range(start, stop, inner_list.len()) {
+        Some((start, stop)) => {
+            inner_list.truncate(stop + 1);
+            inner_list.drain(..start);
+        }
+        None => inner_list.clear(),
+    }
+    // 列表为空时删除key
+    if inner_list.is_empty() {
+        db.remove(key);
+    }
+    Frame::ok()
+}
+
+// LPOS 命令的选项
+struct LposOptions {
+    // 返回第几个匹配的元素，负数表示从尾部开始查找
+    rank: i64,
+    // 返回的匹配数量，0 表示返回所有匹配，None 表示只返回一个下标而不是数组
+    count: Option<usize>,
+    // 最多比较的元素数量，0 表示不限制
+    maxlen: usize,
+}
+
+impl LposOptions {
+    fn parse(args: &[Vec<u8>]) -> Result<LposOptions, Frame> {
+        let mut options = LposOptions {
+            rank: 1,
+            count: None,
+            maxlen: 0,
+        };
+        let mut i = 0;
+        while i < args.len() {
+            let option = String::from_utf8_lossy(&args[i]).to_lowercase();
+            let Some(value) = args.get(i + 1) else {
+                return Err(Frame::error("ERR syntax error"));
+            };
+            let Some(value) = parse_number::<i64>(value) else {
+                return Err(not_integer());
+            };
+            match option.as_str() {
+                "rank" => {
+                    if value == 0 {
+                        return Err(Frame::error(
+                            "ERR RANK can't be zero: use 1 to start from the first match, 2 from the second ... or use negative to start from the end of the list",
+                        ));
+                    }
+                    if value == i64::MIN {
+                        return Err(Frame::error("ERR value is out of range"));
+                    }
+                    options.rank = value;
+                }
+                "count" => {
+                    if value < 0 {
+                        return Err(Frame::error("ERR COUNT can't be negative"));
+                    }
+                    options.count = Some(value as usize);
+                }
+                "maxlen" => {
+                    if value < 0 {
+                        return Err(Frame::error("ERR MAXLEN can't be negative"));
+                    }
+                    options.maxlen = value as usize;
+                }
+                _ => return Err(Frame::error("ERR syntax error")),
+            }
+            i += 2;
         }
+        Ok(options)
+    }
+}
+
+// 处理 LPOS 命令：LPOS key element [RANK rank] [COUNT num-matches] [MAXLEN len]
+// 返回的下标总是从头部开始计算
+pub async fn handle_lpos_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let options = match LposOptions::parse(&parts[3..]) {
+        Ok(options) => options,
+        Err(err) => return err,
+    };
+    let db = client.storage.db.lock().await;
+    let inner_list = match db.get_as::<ListValue>(&parts[1]) {
+        Ok(Some(inner_list)) => inner_list,
+        Ok(None) if options.count.is_some() => return Frame::Array(vec![]),
+        Ok(None) => return Frame::Null,
+        Err(err) => return err,
+    };
+
+    let len = inner_list.len();
+    let maxlen = if options.maxlen == 0 {
+        len
     } else {
-        error!("未找到key");
-    }
-}
-
-// 处理 RPOP 命令，从左侧添加元素
-pub async fn handle_rpop_command(
-    parts: Vec<&str>,
-    socket: &mut tokio::net::TcpStream,
-    hash_table: Arc<Mutex<HashMap<String, VecDeque<String>>>>,
-) {
-    if parts.len() != 2 {
-        error!("命令格式不符合！");
-        socket
-            .write_all("命令格式不符合！".as_bytes())
-            .await
-            .unwrap();
-        return;
-    }
-    let key = parts[1].trim_end_matches('\0').to_string();
-    let mut lock_hash = hash_table.lock().await;
-    if let Some(inner_list) = lock_hash.get_mut(&key) {
-        if let Some(value) = inner_list.pop_back() {
-            socket.write_all(value.as_bytes()).await.unwrap();
+        options.maxlen.min(len)
+    };
+    let indexes: Box<dyn Iterator<Item = usize>> = if options.rank > 0 {
+        Box::new(0..maxlen)
+    } else {
+        Box::new((len - maxlen..len).rev())
+    };
+    let wanted = match options.count {
+        Some(0) => usize::MAX,
+        Some(count) => count,
+        None => 1,
+    };
+    let positions: Vec<usize> = indexes
+        .filter(|index| inner_list[*index] == parts[2])
+        .skip(options.rank.unsigned_abs() as usize - 1)
+        .take(wanted)
+        .collect();
+
+    match options.count {
+        Some(_) => Frame::Array(
+            positions
+                .into_iter()
+                .map(|index| Frame::Integer(index as i64))
+                .collect(),
+        ),
+        None => match positions.first() {
+            Some(index) => Frame::Integer(*index as i64),
+            None => Frame::Null,
+        },
+    }
+}
+
+// LMOVE、RPOPLPUSH 的公共实现，source 不存在时返回空值
+async fn lmove_generic(
+    parts: &[Vec<u8>],
+    client: &mut Client,
+    from: ListEnd,
+    to: ListEnd,
+) -> Frame {
+    let mut db = client.storage.db.lock().await;
+    match move_element(&mut db, &parts[1], &parts[2], from, to) {
+        Ok(Some(value)) => Frame::Bulk(value),
+        Ok(None) => Frame::Null,
+        Err(err) => err,
+    }
+}
+
+// 处理 LMOVE 命令：LMOVE source destination <LEFT | RIGHT> <LEFT | RIGHT>
+pub async fn handle_lmove_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let (Some(from), Some(to)) = (ListEnd::parse(&parts[3]), ListEnd::parse(&parts[4])) else {
+        return Frame::error("ERR syntax error");
+    };
+    lmove_generic(&parts, client, from, to).await
+}
+
+// 处理 RPOPLPUSH 命令，相当于 LMOVE source destination RIGHT LEFT
+pub async fn handle_rpoplpush_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    lmove_generic(&parts, client, ListEnd::Right, ListEnd::Left).await
+}
+
+// 处理 LMPOP 命令，从第一个非空的列表中弹出最多 count 个元素，返回 [key, [元素, ...]]，
+// 所有列表都为空时返回空数组
+pub async fn handle_lmpop_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let MpopArgs { keys, end, count } = match MpopArgs::parse(&parts[1..]) {
+        Ok(args) => args,
+        Err(err) => return err,
+    };
+    let mut db = client.storage.db.lock().await;
+    for key in keys {
+        match pop_elements(&mut db, &key, end, count) {
+            Ok(Some(values)) => return Frame::Array(vec![Frame::Bulk(key), Frame::bulks(values)]),
+            Ok(None) => {}
+            Err(err) => return err,
+        }
+    }
+    Frame::NullArray
+}
+
+// 列表的一端，对应命令中的 LEFT | RIGHT
+#[derive(Clone, Copy)]
+pub enum ListEnd {
+    Left,
+    Right,
+}
+
+impl ListEnd {
+    pub fn parse(arg: &[u8]) -> Option<ListEnd> {
+        if arg.eq_ignore_ascii_case(b"left") {
+            Some(ListEnd::Left)
+        } else if arg.eq_ignore_ascii_case(b"right") {
+            Some(ListEnd::Right)
         } else {
-            error!("元素不存在");
-            socket.write_all("元素不存在".as_bytes()).await.unwrap();
+            None
         }
-    } else {
-        error!("未找到key");
-    }
-}
-
-// 处理 LRANGE 命令，获取列表范围内的所有元素  0,-1返回所有元素
-pub async fn handle_lrange_command(
-    parts: Vec<&str>,
-    socket: &mut tokio::net::TcpStream,
-    hash_table: Arc<Mutex<HashMap<String, VecDeque<String>>>>,
-) {
-    if parts.len() != 4 {
-        error!("命令格式不符合！");
-        socket
-            .write_all("命令格式不符合！".as_bytes())
-            .await
-            .unwrap();
-        return;
-    }
-    let key = parts[1].trim_end_matches('\0').to_string();
-
-    if let Some(inner_list) = hash_table.lock().await.get(&key) {
-        let start = parts[2].trim_end_matches('\0').parse::<i32>().unwrap();
-        let end = parts[3].trim_end_matches('\0').parse::<i32>().unwrap();
-
-        // 处理返回所有的特殊情况
-        if start == 0 && end == -1 {
-            let mut res = String::new();
-            for value in inner_list.iter() {
-                let message = format!("{}\n", value);
-                res.push_str(&message);
-            }
-            socket.write_all(res.as_bytes()).await.unwrap();
-            return;
+    }
+
+    fn pop(self, list: &mut ListValue) -> Option<Vec<u8>> {
+        match self {
+            ListEnd::Left => list.pop_front(),
+            ListEnd::Right => list.pop_back(),
         }
+    }
 
-        let mut res = String::new();
-        for i in start..=end {
-            if let Some(value) = inner_list.get(i as usize) {
-                let message = format!("{}\n", value);
-                res.push_str(&message);
+    fn push(self, list: &mut ListValue, value: Vec<u8>) {
+        match self {
+            ListEnd::Left => list.push_front(value),
+            ListEnd::Right => list.push_back(value),
+        }
+    }
+}
+
+// 从列表的一端弹出最多 count 个元素，列表为空时删除key，key 不存在时返回 Ok(None)
+pub fn pop_elements(
+    db: &mut Db,
+    key: &[u8],
+    end: ListEnd,
+    count: usize,
+) -> Result<Option<Vec<Vec<u8>>>, Frame> {
+    let Some(inner_list) = db.get_mut_as::<ListValue>(key)? else {
+        return Ok(None);
+    };
+    let mut values = Vec::new();
+    while values.len() < count {
+        match end.pop(inner_list) {
+            Some(value) => values.push(value),
+            None => break,
+        }
+    }
+    if inner_list.is_empty() {
+        db.remove(key);
+    }
+    Ok(Some(values))
+}
+
+// 从 source 的 from 端弹出一个元素，放入 destination 的 to 端，返回被移动的元素
+// source 与 destination 相同时相当于旋转列表，source 不存在时返回 Ok(None)
+pub fn move_element(
+    db: &mut Db,
+    source: &[u8],
+    destination: &[u8],
+    from: ListEnd,
+    to: ListEnd,
+) -> Result<Option<Vec<u8>>, Frame> {
+    if db.get_as::<ListValue>(source)?.is_none() {
+        return Ok(None);
+    }
+    db.get_as::<ListValue>(destination)?;
+    let Some(value) = db
+        .get_mut_as::<ListValue>(source)?
+        .and_then(|inner_list| from.pop(inner_list))
+    else {
+        return Ok(None);
+    };
+    to.push(db.get_or_default::<ListValue>(destination)?, value.clone());
+    // 先放入目标再检查源列表，旋转只剩一个元素的列表时不会删除key
+    if db
+        .get_as::<ListValue>(source)?
+        .is_some_and(|inner_list| inner_list.is_empty())
+    {
+        db.remove(source);
+    }
+    db.wake_blocked(destination);
+    Ok(Some(value))
+}
+
+// LMPOP、BLMPOP 中 numkeys key [key ...] <LEFT | RIGHT> [COUNT count] 部分的参数
+pub struct MpopArgs {
+    pub keys: Vec<Vec<u8>>,
+    pub end: ListEnd,
+    pub count: usize,
+}
+
+impl MpopArgs {
+    pub fn parse(args: &[Vec<u8>]) -> Result<MpopArgs, Frame> {
+        let numkeys = match parse_number::<i64>(&args[0]) {
+            Some(numkeys) if numkeys > 0 => numkeys as usize,
+            Some(_) => return Err(Frame::error("ERR numkeys should be greater than 0")),
+            None => return Err(not_integer()),
+        };
+        if numkeys >= args.len() - 1 {
+            return Err(Frame::error("ERR syntax error"));
+        }
+        let keys = args[1..=numkeys].to_vec();
+        let Some(end) = ListEnd::parse(&args[numkeys + 1]) else {
+            return Err(Frame::error("ERR syntax error"));
+        };
+        let count = match &args[numkeys + 2..] {
+            [] => 1,
+            [option, count] if option.eq_ignore_ascii_case(b"count") => {
+                match parse_number::<i64>(count) {
+                    Some(count) if count > 0 => count as usize,
+                    Some(_) => return Err(Frame::error("ERR count should be greater than 0")),
+                    None => return Err(not_integer()),
+                }
             }
+            _ => return Err(Frame::error("ERR syntax error")),
+        };
+        Ok(MpopArgs { keys, end, count })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::operations::testing::{bulk, bulks, client, run};
+    use crate::protocol::frame::Frame;
+
+    #[tokio::test]
+    async fn lrange_follows_redis_index_rules() {
+        let mut client = client();
+        run(&mut client, &["rpush", "l", "a", "b", "c", "d", "e"]).await;
+        let cases: [(&str, &str, &[&str]); 8] = [
+            ("0", "-1", &["a", "b", "c", "d", "e"]),
+            ("-2", "-1", &["d", "e"]),
+            ("-100", "1", &["a", "b"]),
+            ("1", "100", &["b", "c", "d", "e"]),
+            ("3", "1", &[]),
+            ("5", "10", &[]),
+            (
+                "-9223372036854775808",
+                "9223372036854775807",
+                &["a", "b", "c", "d", "e"],
+            ),
+            ("-100", "-6", &[]),
+        ];
+        for (start, stop, expected) in cases {
+            assert_eq!(
+                run(&mut client, &["lrange", "l", start, stop]).await,
+                bulks(expected)
+            );
         }
-        socket.write_all(res.as_bytes()).await.unwrap();
-    } else {
-        error!("未找到key");
+        assert_eq!(
+            run(&mut client, &["lrange", "none", "0", "-1"]).await,
+            bulks(&[])
+        );
+        assert_eq!(
+            run(&mut client, &["lrange", "l", "a", "1"]).await,
+            Frame::error("ERR value is not an integer or out of range")
+        );
+    }
+
+    #[tokio::test]
+    async fn ltrim_keeps_the_range_and_deletes_empty_lists() {
+        let mut client = client();
+        run(&mut client, &["rpush", "l", "a", "b", "c", "d", "e"]).await;
+        assert_eq!(
+            run(&mut client, &["ltrim", "l", "1", "-2"]).await,
+            Frame::ok()
+        );
+        assert_eq!(
+            run(&mut client, &["lrange", "l", "0", "-1"]).await,
+            bulks(&["b", "c", "d"])
+        );
+        assert_eq!(
+            run(&mut client, &["ltrim", "l", "-100", "100"]).await,
+            Frame::ok()
+        );
+        assert_eq!(
+            run(&mut client, &["lrange", "l", "0", "-1"]).await,
+            bulks(&["b", "c", "d"])
+        );
+        assert_eq!(
+            run(&mut client, &["ltrim", "l", "2", "1"]).await,
+            Frame::ok()
+        );
+        assert_eq!(run(&mut client, &["exists", "l"]).await, Frame::Integer(0));
+    }
+
+    #[tokio::test]
+    async fn getkeys_finds_lmpop_keys_after_numkeys() {
+        let mut client = client();
+        assert_eq!(
+            run(
+                &mut client,
+                &["command", "getkeys", "lmpop", "2", "a", "b", "left"]
+            )
+            .await,
+            bulks(&["a", "b"])
+        );
+        assert_eq!(
+            run(
+                &mut client,
+                &[
+                    "command", "getkeys", "lmpop", "1", "k", "left", "count", "2"
+                ]
+            )
+            .await,
+            bulks(&["k"])
+        );
+        assert_eq!(
+            run(
+                &mut client,
+                &["command", "getkeys", "lmpop", "3", "a", "left"]
+            )
+            .await,
+            Frame::error("ERR The command has no key arguments")
+        );
+        assert_eq!(
+            run(
+                &mut client,
+                &["command", "getkeys", "blmpop", "0", "2", "a", "b", "left"]
+            )
+            .await,
+            bulks(&["a", "b"])
+        );
+    }
+
+    #[tokio::test]
+    async fn lmpop_pops_up_to_count_from_the_first_non_empty_list() {
+        let mut client = client();
+        run(&mut client, &["rpush", "b", "1", "2", "3"]).await;
+        assert_eq!(
+            run(
+                &mut client,
+                &["lmpop", "2", "a", "b", "right", "count", "2"]
+            )
+            .await,
+            Frame::Array(vec![bulk("b"), bulks(&["3", "2"])])
+        );
+        assert_eq!(
+            run(&mut client, &["lmpop", "2", "a", "b", "left"]).await,
+            Frame::Array(vec![bulk("b"), bulks(&["1"])])
+        );
+        assert_eq!(run(&mut client, &["exists", "b"]).await, Frame::Integer(0));
+
+        run(&mut client, &["rpush", "c", "x", "y"]).await;
+        assert_eq!(
+            run(&mut client, &["lmpop", "1", "c", "left", "count", "10"]).await,
+            Frame::Array(vec![bulk("c"), bulks(&["x", "y"])])
+        );
+        assert_eq!(run(&mut client, &["exists", "c"]).await, Frame::Integer(0));
+        assert_eq!(
+            run(&mut client, &["lmpop", "1", "c", "left", "count", "0"]).await,
+            Frame::error("ERR count should be greater than 0")
+        );
+        assert_eq!(
+            run(&mut client, &["lmpop", "1", "c", "left", "count"]).await,
+            Frame::error("ERR syntax error")
+        );
+    }
+
+    #[tokio::test]
+    async fn lmpop_on_empty_lists_replies_with_null_array() {
+        let mut client = client();
+        assert_eq!(
+            run(&mut client, &["lmpop", "2", "a", "b", "left"]).await,
+            Frame::NullArray
+        );
     }
 }
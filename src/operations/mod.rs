@@ -1,7 +1,16 @@
+pub mod expire;
+pub mod generic;
+pub mod glob;
 pub mod hash;
 pub mod list;
 pub mod set;
 pub mod sorted_set;
 pub mod string;
 pub mod help;
+pub mod scan;
 pub mod bitmap;
+pub mod utils;
+pub mod connection;
+pub mod server;
+#[cfg(test)]
+pub mod testing;
@@ -0,0 +1,66 @@
+use crate::operations::glob::glob_match;
+use crate::operations::utils::{not_integer, parse_number};
+use crate::protocol::frame::Frame;
+
+// 未指定 COUNT 时每次迭代的元素个数
+const DEFAULT_COUNT: usize = 10;
+
+// SCAN、HSCAN、SSCAN、ZSCAN 共用的参数
+pub struct ScanOptions {
+    pub cursor: u64,
+    pub pattern: Option<Vec<u8>>,
+    pub count: usize,
+    // 只有 SCAN 支持 TYPE 选项
+    pub type_name: Option<String>,
+}
+
+impl ScanOptions {
+    // 解析 cursor 以及之后的 MATCH、COUNT、TYPE 选项
+    pub fn parse(args: &[Vec<u8>], allow_type: bool) -> Result<ScanOptions, Frame> {
+        let cursor =
+            parse_number::<u64>(&args[0]).ok_or_else(|| Frame::error("ERR invalid cursor"))?;
+        let mut options = ScanOptions {
+            cursor,
+            pattern: None,
+            count: DEFAULT_COUNT,
+            type_name: None,
+        };
+        let mut i = 1;
+        while i < args.len() {
+            let Some(value) = args.get(i + 1) else {
+                return Err(Frame::error("ERR syntax error"));
+            };
+            let option = String::from_utf8_lossy(&args[i]).to_lowercase();
+            match option.as_str() {
+                "match" => options.pattern = Some(value.clone()),
+                "count" => match parse_number::<i64>(value) {
+                    Some(count) if count >= 1 => options.count = count as usize,
+                    Some(_) => return Err(Frame::error("ERR syntax error")),
+                    None => return Err(not_integer()),
+                },
+                "type" if allow_type => {
+                    options.type_name = Some(String::from_utf8_lossy(value).to_lowercase())
+                }
+                _ => return Err(Frame::error("ERR syntax error")),
+            }
+            i += 2;
+        }
+        Ok(options)
+    }
+
+    // 是否满足 MATCH 条件
+    pub fn matches(&self, item: &[u8]) -> bool {
+        match &self.pattern {
+            Some(pattern) => glob_match(pattern, item),
+            None => true,
+        }
+    }
+}
+
+// [游标, 元素数组] 形式的回复
+pub fn scan_reply(cursor: u64, items: Vec<Frame>) -> Frame {
+    Frame::Array(vec![
+        Frame::Bulk(cursor.to_string().into_bytes()),
+        Frame::Array(items),
+    ])
+}
@@ -0,0 +1,233 @@
+use crate::command::spec::CommandFlag::{Blocking, Fast, ReadOnly, Write};
+use crate::command::spec::{CommandGroup, CommandSpec, SubcommandDoc};
+use crate::command::table::{COMMAND_TABLE, CommandTable};
+use crate::connection::client::Client;
+use crate::protocol::frame::Frame;
+
+// 注册服务端相关的命令
+pub fn register(table: &mut CommandTable) {
+    table.register(
+        CommandSpec::new("command", -1, &[], handle_command_command)
+            .syntax("[subcommand [arg ...]]")
+            .doc(
+                CommandGroup::Server,
+                "2.8.13",
+                "O(N) where N is the total number of Redis commands",
+                "Returns detailed information about all commands.",
+            )
+            .subcommand("count", 2, "", "Returns a count of commands.")
+            .subcommand(
+                "info",
+                -2,
+                "[command-name ...]",
+                "Returns information about one, multiple or all commands.",
+            )
+            .subcommand(
+                "docs",
+                -2,
+                "[command-name ...]",
+                "Returns documentary information about one, multiple or all commands.",
+            )
+            .subcommand(
+                "getkeys",
+                -3,
+                "command [arg ...]",
+                "Extracts the key names from an arbitrary command.",
+            ),
+    );
+}
+
+fn bulk(s: &str) -> Frame {
+    Frame::Bulk(s.as_bytes().to_vec())
+}
+
+// 单个命令的 COMMAND INFO 信息
+// [name, arity, flags, first_key, last_key, step, acl_categories, tips, key_specs, subcommands]
+fn command_info(spec: &CommandSpec) -> Frame {
+    let mut flags: Vec<Frame> = spec
+        .flags
+        .iter()
+        .map(|flag| Frame::Simple(flag.name().to_string()))
+        .collect();
+    if spec.key_finder.is_some() {
+        flags.push(Frame::Simple("movablekeys".to_string()));
+    }
+    Frame::Array(vec![
+        bulk(spec.name),
+        Frame::Integer(spec.arity as i64),
+        Frame::Set(flags),
+        Frame::Integer(spec.first_key as i64),
+        Frame::Integer(spec.last_key as i64),
+        Frame::Integer(spec.step as i64),
+        Frame::Set(acl_categories(spec)),
+        Frame::Array(vec![]),
+        Frame::Array(vec![]),
+        Frame::Array(
+            spec.subcommands
+                .iter()
+                .map(|sub| subcommand_info(spec, sub))
+                .collect(),
+        ),
+    ])
+}
+
+// 子命令的 COMMAND INFO 信息，格式与命令相同，名字为 "命令|子命令"，
+// 标志和 ACL 分类沿用所属的命令，子命令不再有子命令
+fn subcommand_info(spec: &CommandSpec, sub: &SubcommandDoc) -> Frame {
+    Frame::Array(vec![
+        bulk(&format!("{}|{}", spec.name, sub.name)),
+        Frame::Integer(sub.arity as i64),
+        Frame::Set(
+            spec.flags
+                .iter()
+                .map(|flag| Frame::Simple(flag.name().to_string()))
+                .collect(),
+        ),
+        Frame::Integer(0),
+        Frame::Integer(0),
+        Frame::Integer(0),
+        Frame::Set(acl_categories(spec)),
+        Frame::Array(vec![]),
+        Frame::Array(vec![]),
+        Frame::Array(vec![]),
+    ])
+}
+
+// 根据分组和标志推导 ACL 分类
+fn acl_categories(spec: &CommandSpec) -> Vec<Frame> {
+    let mut categories = Vec::new();
+    match spec.group {
+        CommandGroup::Generic => categories.push("@keyspace".to_string()),
+        CommandGroup::SortedSet => categories.push("@sortedset".to_string()),
+        CommandGroup::Server => {}
+        group => categories.push(format!("@{}", group.name())),
+    }
+    if spec.has_flag(Write) {
+        categories.push("@write".to_string());
+    }
+    if spec.has_flag(ReadOnly) {
+        categories.push("@read".to_string());
+    }
+    if spec.has_flag(Fast) {
+        categories.push("@fast".to_string());
+    } else {
+        categories.push("@slow".to_string());
+    }
+    if spec.has_flag(Blocking) {
+        categories.push("@blocking".to_string());
+    }
+    categories.into_iter().map(Frame::Simple).collect()
+}
+
+// 单个命令的 COMMAND DOCS 信息
+fn command_docs(spec: &CommandSpec) -> Frame {
+    Frame::Map(vec![
+        (bulk("summary"), bulk(spec.summary)),
+        (bulk("since"), bulk(spec.since)),
+        (bulk("group"), bulk(spec.group.name())),
+        (bulk("complexity"), bulk(spec.complexity)),
+        (bulk("syntax"), bulk(&spec.usage())),
+    ])
+}
+
+// 按命令名排序，保证输出稳定
+fn sorted_specs() -> Vec<&'static CommandSpec> {
+    let mut specs: Vec<_> = COMMAND_TABLE.iter().collect();
+    specs.sort_by_key(|spec| spec.name);
+    specs
+}
+
+// 处理 COMMAND 命令：COMMAND [COUNT | INFO [name ...] | DOCS [name ...] | GETKEYS command [arg ...]]
+pub async fn handle_command_command(parts: Vec<Vec<u8>>, _client: &mut Client) -> Frame {
+    let Some(subcommand) = parts.get(1) else {
+        return Frame::Array(sorted_specs().into_iter().map(command_info).collect());
+    };
+    let subcommand = String::from_utf8_lossy(subcommand).to_lowercase();
+    match subcommand.as_str() {
+        "count" if parts.len() == 2 => Frame::Integer(COMMAND_TABLE.len() as i64),
+        "info" => {
+            if parts.len() == 2 {
+                return Frame::Array(sorted_specs().into_iter().map(command_info).collect());
+            }
+            Frame::Array(
+                parts[2..]
+                    .iter()
+                    .map(|name| match COMMAND_TABLE.lookup(name) {
+                        Some(spec) => command_info(spec),
+                        None => Frame::Null,
+                    })
+                    .collect(),
+            )
+        }
+        "docs" => {
+            let specs = if parts.len() == 2 {
+                sorted_specs()
+            } else {
+                parts[2..]
+                    .iter()
+                    .filter_map(|name| COMMAND_TABLE.lookup(name))
+                    .collect()
+            };
+            Frame::Map(
+                specs
+                    .into_iter()
+                    .map(|spec| (bulk(spec.name), command_docs(spec)))
+                    .collect(),
+            )
+        }
+        "getkeys" if parts.len() >= 3 => {
+            let args = &parts[2..];
+            let Some(spec) = COMMAND_TABLE.lookup(&args[0]) else {
+                return Frame::error("ERR Invalid command specified");
+            };
+            if !spec.check_arity(args.len()) {
+                return Frame::error("ERR Invalid number of arguments specified for command");
+            }
+            let positions = spec.key_positions(args);
+            if positions.is_empty() {
+                return Frame::error("ERR The command has no key arguments");
+            }
+            Frame::bulks(positions.into_iter().map(|i| args[i].clone()))
+        }
+        _ => Frame::error(format!(
+            "ERR unknown subcommand or wrong number of arguments for '{}'. Try COMMAND HELP.",
+            String::from_utf8_lossy(&parts[1])
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::operations::testing::{bulk, client, run};
+    use crate::protocol::frame::Frame;
+
+    #[tokio::test]
+    async fn command_info_nests_subcommand_info() {
+        let mut client = client();
+        let Frame::Array(infos) = run(&mut client, &["command", "info", "command"]).await else {
+            panic!("COMMAND INFO should reply with an array");
+        };
+        let Some(Frame::Array(info)) = infos.first() else {
+            panic!("COMMAND INFO command should describe the command");
+        };
+        let Some(Frame::Array(subcommands)) = info.get(9) else {
+            panic!("the tenth field should list the subcommands");
+        };
+        assert_eq!(subcommands.len(), 4);
+        let Frame::Array(count) = &subcommands[0] else {
+            panic!("each subcommand should be a full command info array");
+        };
+        assert_eq!(count.len(), 10);
+        assert_eq!(count[0], bulk("command|count"));
+        assert_eq!(count[1], Frame::Integer(2));
+        assert_eq!(count[9], Frame::Array(vec![]));
+        let arities: Vec<Frame> = subcommands
+            .iter()
+            .map(|sub| match sub {
+                Frame::Array(sub) => sub[1].clone(),
+                other => other.clone(),
+            })
+            .collect();
+        assert_eq!(arities, [2, -2, -2, -3].map(Frame::Integer).to_vec());
+    }
+}
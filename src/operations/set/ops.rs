@@ -1,133 +1,443 @@
-use log::error;
-use std::collections::{HashMap, HashSet};
-use std::sync::Arc;
-use tokio::io::AsyncWriteExt;
-use tokio::sync::Mutex;
-
-// 处理 SADD 命令
-pub async fn handle_sadd_command(
-    parts: Vec<&str>,
-    socket: &mut tokio::net::TcpStream,
-    storage: Arc<Mutex<HashMap<String, HashSet<String>>>>,
-) {
-    if parts.len() <= 2 {
-        error!("命令格式不符合！");
-        socket
-            .write_all("命令格式不符合！".as_bytes())
-            .await
-            .unwrap();
-        return;
-    }
+use crate::command::spec::CommandFlag::{Fast, ReadOnly, Write};
+use crate::command::spec::{CommandGroup, CommandSpec, numkeys_positions};
+use crate::command::table::CommandTable;
+use crate::connection::client::Client;
+use crate::db::storage::Db;
+use crate::db::value::{SetValue, Value};
+use crate::operations::scan::{ScanOptions, scan_reply};
+use crate::operations::utils::{not_integer, parse_number};
+use crate::protocol::frame::Frame;
+use std::collections::HashSet;
 
-    let key = parts[1].trim_end_matches('\0').to_string();
-    let values: Vec<_> = parts[2..]
-        .iter()
-        .map(|s| s.trim_end_matches('\0').to_string())
-        .collect();
+// 注册 set 类型的命令
+pub fn register(table: &mut CommandTable) {
+    table.register(
+        CommandSpec::new("sadd", -3, &[Write, Fast], handle_sadd_command)
+            .keys(1, 1, 1)
+            .syntax("key member [member ...]")
+            .doc(
+                CommandGroup::Set,
+                "1.0.0",
+                "O(1) for each element added, so O(N) to add N elements when the command is called with multiple arguments.",
+                "Adds one or more members to a set. Creates the key if it doesn't exist.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("sismember", 3, &[ReadOnly, Fast], handle_sismember_command)
+            .keys(1, 1, 1)
+            .syntax("key member")
+            .doc(
+                CommandGroup::Set,
+                "1.0.0",
+                "O(1)",
+                "Determines whether a member belongs to a set.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("smembers", 2, &[ReadOnly], handle_smembers_command)
+            .keys(1, 1, 1)
+            .syntax("key")
+            .doc(
+                CommandGroup::Set,
+                "1.0.0",
+                "O(N) where N is the set cardinality.",
+                "Returns all members of a set.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("srem", -3, &[Write, Fast], handle_srem_command)
+            .keys(1, 1, 1)
+            .syntax("key member [member ...]")
+            .doc(
+                CommandGroup::Set,
+                "1.0.0",
+                "O(N) where N is the number of members to be removed.",
+                "Removes one or more members from a set. Deletes the set if the last member was removed.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("sscan", -3, &[ReadOnly], handle_sscan_command)
+            .keys(1, 1, 1)
+            .syntax("key cursor [MATCH pattern] [COUNT count]")
+            .doc(
+                CommandGroup::Set,
+                "2.8.0",
+                "O(log N + COUNT) for every call. O(N) for a complete iteration.",
+                "Iterates over members of a set.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("sinter", -2, &[ReadOnly], handle_sinter_command)
+            .keys(1, -1, 1)
+            .syntax("key [key ...]")
+            .doc(
+                CommandGroup::Set,
+                "1.0.0",
+                "O(N*M) worst case where N is the cardinality of the smallest set and M is the number of sets.",
+                "Returns the intersect of multiple sets.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("sinterstore", -3, &[Write], handle_sinterstore_command)
+            .keys(1, -1, 1)
+            .syntax("destination key [key ...]")
+            .doc(
+                CommandGroup::Set,
+                "1.0.0",
+                "O(N*M) worst case where N is the cardinality of the smallest set and M is the number of sets.",
+                "Stores the intersect of multiple sets in a key.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("sintercard", -3, &[ReadOnly], handle_sintercard_command)
+            .movable_keys(numkeys_positions::<1>)
+            .syntax("numkeys key [key ...] [LIMIT limit]")
+            .doc(
+                CommandGroup::Set,
+                "7.0.0",
+                "O(N*M) worst case where N is the cardinality of the smallest set and M is the number of sets.",
+                "Returns the number of members of the intersect of multiple sets.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("sunion", -2, &[ReadOnly], handle_sunion_command)
+            .keys(1, -1, 1)
+            .syntax("key [key ...]")
+            .doc(
+                CommandGroup::Set,
+                "1.0.0",
+                "O(N) where N is the total number of elements in all given sets.",
+                "Returns the union of multiple sets.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("sunionstore", -3, &[Write], handle_sunionstore_command)
+            .keys(1, -1, 1)
+            .syntax("destination key [key ...]")
+            .doc(
+                CommandGroup::Set,
+                "1.0.0",
+                "O(N) where N is the total number of elements in all given sets.",
+                "Stores the union of multiple sets in a key.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("sdiff", -2, &[ReadOnly], handle_sdiff_command)
+            .keys(1, -1, 1)
+            .syntax("key [key ...]")
+            .doc(
+                CommandGroup::Set,
+                "1.0.0",
+                "O(N) where N is the total number of elements in all given sets.",
+                "Returns the difference of multiple sets.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("sdiffstore", -3, &[Write], handle_sdiffstore_command)
+            .keys(1, -1, 1)
+            .syntax("destination key [key ...]")
+            .doc(
+                CommandGroup::Set,
+                "1.0.0",
+                "O(N) where N is the total number of elements in all given sets.",
+                "Stores the difference of multiple sets in a key.",
+            ),
+    );
+}
 
-    // 获取可变的哈希表锁
-    let mut map = storage.lock().await;
-    if let Some(set) = map.get_mut(&key) {
-        for value in values {
-            set.insert(value);
+// 处理 SADD 命令，返回新增的元素数量
+pub async fn handle_sadd_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let mut db = client.storage.db.lock().await;
+    let set = match db.get_or_default::<SetValue>(&parts[1]) {
+        Ok(set) => set,
+        Err(err) => return err,
+    };
+    let mut added = 0;
+    for value in &parts[2..] {
+        if set.insert(value.clone()) {
+            added += 1;
         }
-    } else {
-        let mut set = HashSet::new();
-        for value in values {
-            set.insert(value);
-        }
-        map.insert(key, set);
     }
-    socket.write_all("ok".as_bytes()).await.unwrap();
+    Frame::Integer(added)
 }
 
 // 处理 SISMEMBER 命令
-pub async fn handle_sismember_command(
-    parts: Vec<&str>,
-    socket: &mut tokio::net::TcpStream,
-    storage: Arc<Mutex<HashMap<String, HashSet<String>>>>,
-) {
-    if parts.len() != 3 {
-        error!("命令格式不符合！");
-        socket
-            .write_all("命令格式不符合！".as_bytes())
-            .await
-            .unwrap();
-        return;
+pub async fn handle_sismember_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let db = client.storage.db.lock().await;
+    match db.get_as::<SetValue>(&parts[1]) {
+        Ok(Some(set)) => Frame::Integer(set.contains(&parts[2]) as i64),
+        Ok(None) => Frame::Integer(0),
+        Err(err) => err,
     }
+}
 
-    let key = parts[1].trim_end_matches('\0').to_string();
-    let value = parts[2].trim_end_matches('\0').to_string();
-    let map = storage.lock().await;
-    if let Some(set) = map.get(&key) {
-        if set.contains(&value) {
-            socket.write_all("1".as_bytes()).await.unwrap();
-        } else {
-            socket.write_all("0".as_bytes()).await.unwrap();
+// 处理 SMEMBERS 命令
+pub async fn handle_smembers_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let db = client.storage.db.lock().await;
+    match db.get_as::<SetValue>(&parts[1]) {
+        Ok(Some(set)) => {
+            let mut values = set.iter().cloned().collect::<Vec<_>>();
+            values.sort();
+            Frame::Set(values.into_iter().map(Frame::Bulk).collect())
         }
-    } else {
-        socket.write_all("0".as_bytes()).await.unwrap();
+        Ok(None) => Frame::Set(vec![]),
+        Err(err) => err,
     }
 }
 
-// 处理 SMEMBERS 命令
-pub async fn handle_smembers_command(
-    parts: Vec<&str>,
-    socket: &mut tokio::net::TcpStream,
-    storage: Arc<Mutex<HashMap<String, HashSet<String>>>>,
-) {
-    if parts.len() != 2 {
-        error!("命令格式不符合！");
-        socket
-            .write_all("命令格式不符合！".as_bytes())
-            .await
-            .unwrap();
-        return;
+// 处理 SREM 命令，返回被删除的元素数量
+pub async fn handle_srem_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let key = &parts[1];
+    let mut db = client.storage.db.lock().await;
+    let set = match db.get_mut_as::<SetValue>(key) {
+        Ok(Some(set)) => set,
+        Ok(None) => return Frame::Integer(0),
+        Err(err) => return err,
+    };
+    let mut count = 0;
+    for value in &parts[2..] {
+        if set.remove(value) {
+            count += 1;
+        }
+    }
+    // 集合为空时删除key
+    if set.is_empty() {
+        db.remove(key);
     }
+    Frame::Integer(count)
+}
 
-    let key = parts[1].trim_end_matches('\0').to_string();
-    let map = storage.lock().await;
-    if let Some(set) = map.get(&key) {
-        let mut values = set.iter().map(|s| s.as_str()).collect::<Vec<_>>();
-        values.sort();
-        let result = values.join(" ");
-        socket.write_all(result.as_bytes()).await.unwrap();
-    } else {
-        socket.write_all("".as_bytes()).await.unwrap();
+// 处理 SSCAN 命令，返回 [游标, [member, ...]]
+pub async fn handle_sscan_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let options = match ScanOptions::parse(&parts[2..], false) {
+        Ok(options) => options,
+        Err(err) => return err,
+    };
+    let db = client.storage.db.lock().await;
+    let set = match db.get_as::<SetValue>(&parts[1]) {
+        Ok(Some(set)) => set,
+        Ok(None) => return scan_reply(0, vec![]),
+        Err(err) => return err,
+    };
+    let (cursor, members) = set.scan(options.cursor, options.count);
+    let res = members
+        .into_iter()
+        .filter(|member| options.matches(member))
+        .map(|member| Frame::Bulk(member.clone()))
+        .collect();
+    scan_reply(cursor, res)
+}
+
+// 集合运算的类型
+#[derive(Clone, Copy)]
+enum SetOp {
+    Inter,
+    Union,
+    Diff,
+}
+
+// 读取所有 key 对应的集合，不存在的 key 视为空集，任意一个 key 类型不符时返回错误
+fn load_sets<'a>(db: &'a Db, keys: &[Vec<u8>]) -> Result<Vec<Option<&'a SetValue>>, Frame> {
+    keys.iter().map(|key| db.get_as::<SetValue>(key)).collect()
+}
+
+// 求交集，从最小的集合开始遍历，找到 limit 个成员后停止，limit 为 0 表示不限制
+fn intersect<'a>(sets: &[Option<&'a SetValue>], limit: usize) -> Vec<&'a Vec<u8>> {
+    // 有一个集合为空时交集一定为空
+    let Some(mut sets) = sets.iter().copied().collect::<Option<Vec<_>>>() else {
+        return vec![];
+    };
+    sets.sort_by_key(|set| set.len());
+    let Some((smallest, others)) = sets.split_first() else {
+        return vec![];
+    };
+    let limit = if limit == 0 { usize::MAX } else { limit };
+    smallest
+        .iter()
+        .filter(|member| others.iter().all(|set| set.contains(member)))
+        .take(limit)
+        .collect()
+}
+
+// 对 keys 对应的集合做集合运算，返回结果中的成员
+fn set_algebra<'a>(db: &'a Db, keys: &[Vec<u8>], op: SetOp) -> Result<Vec<&'a Vec<u8>>, Frame> {
+    let sets = load_sets(db, keys)?;
+    let members = match op {
+        SetOp::Inter => intersect(&sets, 0),
+        SetOp::Union => {
+            let mut seen = HashSet::new();
+            sets.iter()
+                .flatten()
+                .flat_map(|set| set.iter())
+                .filter(|member| seen.insert(*member))
+                .collect()
+        }
+        // 第一个集合中不属于其余任何集合的成员
+        SetOp::Diff => match sets.split_first() {
+            Some((Some(first), others)) => first
+                .iter()
+                .filter(|member| others.iter().flatten().all(|set| !set.contains(member)))
+                .collect(),
+            _ => vec![],
+        },
+    };
+    Ok(members)
+}
+
+// SINTER、SUNION、SDIFF 的公共实现，成员排序后返回，保证输出稳定
+async fn set_algebra_generic(parts: &[Vec<u8>], client: &mut Client, op: SetOp) -> Frame {
+    let db = client.storage.db.lock().await;
+    match set_algebra(&db, &parts[1..], op) {
+        Ok(mut members) => {
+            members.sort();
+            Frame::Set(members.into_iter().cloned().map(Frame::Bulk).collect())
+        }
+        Err(err) => err,
     }
 }
 
-// 处理 SREM 命令
-pub async fn handle_srem_command(
-    parts: Vec<&str>,
-    socket: &mut tokio::net::TcpStream,
-    storage: Arc<Mutex<HashMap<String, HashSet<String>>>>,
-) {
-    if parts.len() <= 2 {
-        error!("命令格式不符合！");
-        socket
-            .write_all("命令格式不符合！".as_bytes())
-            .await
-            .unwrap();
-        return;
+// SINTERSTORE、SUNIONSTORE、SDIFFSTORE 的公共实现，结果覆盖 destination 原来的值，
+// 结果为空时删除 destination，返回结果的成员数量
+async fn set_algebra_store_generic(parts: &[Vec<u8>], client: &mut Client, op: SetOp) -> Frame {
+    let mut db = client.storage.db.lock().await;
+    let set: SetValue = match set_algebra(&db, &parts[2..], op) {
+        Ok(members) => members.into_iter().cloned().collect(),
+        Err(err) => return err,
+    };
+    let len = set.len();
+    if set.is_empty() {
+        db.remove(&parts[1]);
+    } else {
+        db.insert(parts[1].clone(), Value::Set(set));
     }
+    Frame::Integer(len as i64)
+}
 
-    let key = parts[1].trim_end_matches('\0').to_string();
-    let values: Vec<_> = parts[2..]
-        .iter()
-        .map(|s| s.trim_end_matches('\0').to_string())
-        .collect();
-    let mut map = storage.lock().await;
-    let mut count = 0;
-    if let Some(set) = map.get_mut(&key) {
-        for value in values {
-            if set.remove(&value) {
-                count += 1;
+// 处理 SINTER 命令
+pub async fn handle_sinter_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    set_algebra_generic(&parts, client, SetOp::Inter).await
+}
+
+// 处理 SINTERSTORE 命令
+pub async fn handle_sinterstore_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    set_algebra_store_generic(&parts, client, SetOp::Inter).await
+}
+
+// 处理 SUNION 命令
+pub async fn handle_sunion_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    set_algebra_generic(&parts, client, SetOp::Union).await
+}
+
+// 处理 SUNIONSTORE 命令
+pub async fn handle_sunionstore_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    set_algebra_store_generic(&parts, client, SetOp::Union).await
+}
+
+// 处理 SDIFF 命令
+pub async fn handle_sdiff_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    set_algebra_generic(&parts, client, SetOp::Diff).await
+}
+
+// 处理 SDIFFSTORE 命令
+pub async fn handle_sdiffstore_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    set_algebra_store_generic(&parts, client, SetOp::Diff).await
+}
+
+// 处理 SINTERCARD 命令：SINTERCARD numkeys key [key ...] [LIMIT limit]
+// 只计算交集的成员数量，达到 limit 后停止，limit 为 0 表示不限制
+pub async fn handle_sintercard_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let numkeys = match parse_number::<i64>(&parts[1]) {
+        Some(numkeys) if numkeys > 0 => numkeys as usize,
+        Some(_) => return Frame::error("ERR numkeys should be greater than 0"),
+        None => return not_integer(),
+    };
+    if numkeys > parts.len() - 2 {
+        return Frame::error("ERR Number of keys can't be greater than number of args");
+    }
+    let keys = &parts[2..2 + numkeys];
+    let limit = match &parts[2 + numkeys..] {
+        [] => 0,
+        [option, limit] if option.eq_ignore_ascii_case(b"limit") => {
+            match parse_number::<i64>(limit) {
+                Some(limit) if limit >= 0 => limit as usize,
+                Some(_) => return Frame::error("ERR LIMIT can't be negative"),
+                None => return not_integer(),
             }
         }
-        let res = format!("删除{}个元素", count);
-        socket.write_all(res.as_bytes()).await.unwrap();
-    } else {
-        socket.write_all("未找到key".as_bytes()).await.unwrap();
+        _ => return Frame::error("ERR syntax error"),
+    };
+    let db = client.storage.db.lock().await;
+    match load_sets(&db, keys) {
+        Ok(sets) => Frame::Integer(intersect(&sets, limit).len() as i64),
+        Err(err) => err,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::operations::testing::{bulks, client, run};
+    use crate::protocol::frame::Frame;
+
+    #[tokio::test]
+    async fn getkeys_finds_sintercard_keys_after_numkeys() {
+        let mut client = client();
+        assert_eq!(
+            run(
+                &mut client,
+                &[
+                    "command",
+                    "getkeys",
+                    "sintercard",
+                    "2",
+                    "a",
+                    "b",
+                    "limit",
+                    "1"
+                ]
+            )
+            .await,
+            bulks(&["a", "b"])
+        );
+        assert_eq!(
+            run(&mut client, &["command", "getkeys", "sintercard", "0", "a"]).await,
+            Frame::error("ERR The command has no key arguments")
+        );
+    }
+
+    #[tokio::test]
+    async fn sintercard_counts_the_intersection_up_to_limit() {
+        let mut client = client();
+        run(&mut client, &["sadd", "a", "1", "2", "3", "4"]).await;
+        run(&mut client, &["sadd", "b", "2", "3", "4", "5"]).await;
+        let cases: [(&[&str], i64); 6] = [
+            (&["2", "a", "b"], 3),
+            (&["2", "a", "b", "limit", "0"], 3),
+            (&["2", "a", "b", "limit", "2"], 2),
+            (&["2", "a", "b", "LIMIT", "10"], 3),
+            (&["1", "a"], 4),
+            (&["2", "a", "missing"], 0),
+        ];
+        for (args, expected) in cases {
+            let mut command = vec!["sintercard"];
+            command.extend_from_slice(args);
+            assert_eq!(run(&mut client, &command).await, Frame::Integer(expected));
+        }
+        assert_eq!(
+            run(&mut client, &["sintercard", "2", "a", "b", "limit", "-1"]).await,
+            Frame::error("ERR LIMIT can't be negative")
+        );
+        assert_eq!(
+            run(&mut client, &["sintercard", "3", "a", "b"]).await,
+            Frame::error("ERR Number of keys can't be greater than number of args")
+        );
+        assert_eq!(
+            run(&mut client, &["sintercard", "0", "a"]).await,
+            Frame::error("ERR numkeys should be greater than 0")
+        );
+        run(&mut client, &["set", "s", "v"]).await;
+        assert_eq!(
+            run(&mut client, &["sintercard", "2", "a", "s"]).await,
+            Frame::error("WRONGTYPE Operation against a key holding the wrong kind of value")
+        );
     }
 }
@@ -1,175 +1,228 @@
-use log::error;
-use skiplist::OrderedSkipList;
-use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::io::AsyncWriteExt;
-use tokio::sync::Mutex;
+use crate::command::spec::CommandFlag::{Fast, ReadOnly, Write};
+use crate::command::spec::{CommandGroup, CommandSpec};
+use crate::command::table::CommandTable;
+use crate::connection::client::Client;
+use crate::db::value::SortedSet;
+use crate::operations::scan::{ScanOptions, scan_reply};
+use crate::operations::utils::{
+    format_float, normalize_range, not_float, not_integer, parse_number, wrong_args,
+};
+use crate::protocol::frame::Frame;
+use std::ops::Bound;
 
-// 处理 ZADD 命令
-pub async fn handle_zadd_command(
-    parts: Vec<&str>,
-    socket: &mut tokio::net::TcpStream,
-    hash_table: Arc<Mutex<HashMap<String, OrderedSkipList<(f64, String)>>>>,
-    hash: Arc<Mutex<HashMap<String, HashMap<String, f64>>>>,
-) {
-    if parts.len() <= 3 || parts.len() % 2 != 0 {
-        error!("命令格式不符合！");
-        socket
-            .write_all("命令格式不符合！".as_bytes())
-            .await
-            .unwrap();
-        return;
+// 注册 sorted_set 类型的命令
+pub fn register(table: &mut CommandTable) {
+    table.register(
+        CommandSpec::new("zadd", -4, &[Write, Fast], handle_zadd_command)
+            .keys(1, 1, 1)
+            .syntax("key score member [score member ...]")
+            .doc(
+                CommandGroup::SortedSet,
+                "1.2.0",
+                "O(log(N)) for each item added, where N is the number of elements in the sorted set.",
+                "Adds one or more members to a sorted set, or updates their scores. Creates the key if it doesn't exist.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("zrange", -4, &[ReadOnly], handle_zrange_command)
+            .keys(1, 1, 1)
+            .syntax("key start stop [WITHSCORES]")
+            .doc(
+                CommandGroup::SortedSet,
+                "1.2.0",
+                "O(log(N)+M) with N being the number of elements in the sorted set and M the number of elements returned.",
+                "Returns members in a sorted set within a range of indexes.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("zrem", 3, &[Write, Fast], handle_zrem_command)
+            .keys(1, 1, 1)
+            .syntax("key member")
+            .doc(
+                CommandGroup::SortedSet,
+                "1.2.0",
+                "O(log(N)) with N being the number of elements in the sorted set.",
+                "Removes a member from a sorted set. Deletes the sorted set if the last member was removed.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("zscore", 3, &[ReadOnly, Fast], handle_zscore_command)
+            .keys(1, 1, 1)
+            .syntax("key member")
+            .doc(
+                CommandGroup::SortedSet,
+                "1.2.0",
+                "O(1)",
+                "Returns the score of a member in a sorted set.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("zscan", -3, &[ReadOnly], handle_zscan_command)
+            .keys(1, 1, 1)
+            .syntax("key cursor [MATCH pattern] [COUNT count]")
+            .doc(
+                CommandGroup::SortedSet,
+                "2.8.0",
+                "O(log N + COUNT) for every call. O(N) for a complete iteration.",
+                "Iterates over members and scores of a sorted set.",
+            ),
+    );
+}
+
+// 处理 ZADD 命令，返回新增的成员数量
+pub async fn handle_zadd_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    // score 和 member 必须成对出现
+    if !parts.len().is_multiple_of(2) {
+        return wrong_args("zadd");
     }
-    let key = parts[1].trim_end_matches('\0').to_string();
     let mut score_member_pairs = Vec::new();
-    for i in (2..parts.len()).step_by(2) {
-        let score = parts[i].trim_end_matches('\0').parse::<f64>().unwrap();
-        let member = parts[i + 1].trim_end_matches('\0').to_string();
-        score_member_pairs.push((score, member));
+    for pair in parts[2..].chunks(2) {
+        let score = match parse_number::<f64>(&pair[0]) {
+            Some(score) if !score.is_nan() => score,
+            _ => return not_float(),
+        };
+        score_member_pairs.push((score, pair[1].clone()));
     }
-    let mut map = hash_table.lock().await;
-    let mut hash_map = hash.lock().await;
-
-    let skip_key = key.clone();
-    let smp = score_member_pairs.clone();
-    // 存储数据到跳表中
-    if let Some(set) = map.get_mut(&skip_key) {
-        for (score, member) in smp {
-            set.insert((score, member.clone()));
-        }
-    } else {
-        let mut set = OrderedSkipList::new();
-        for (score, member) in smp {
-            set.insert((score, member));
+    let mut db = client.storage.db.lock().await;
+    let zset = match db.get_or_default::<SortedSet>(&parts[1]) {
+        Ok(zset) => zset,
+        Err(err) => return err,
+    };
+    let mut added = 0;
+    for (score, member) in score_member_pairs {
+        if zset.insert(score, member) {
+            added += 1;
         }
-        map.insert(skip_key, set);
-    }
-    let hash_key = key.clone();
-    let hash_smp = score_member_pairs.clone();
-    // 存储数据到hash中
-    let inner_map = hash_map.entry(hash_key).or_insert(HashMap::new());
-    for (score, member) in hash_smp {
-        inner_map.insert(member, score);
     }
 
-    socket.write_all("ok".as_bytes()).await.unwrap();
+    Frame::Integer(added)
 }
 
 // 处理 ZRANGE 命令
-pub async fn handle_zrange_command(
-    parts: Vec<&str>,
-    socket: &mut tokio::net::TcpStream,
-    hash_table: Arc<Mutex<HashMap<String, OrderedSkipList<(f64, String)>>>>,
-) {
-    if parts.len() != 4 {
-        error!("命令格式不符合！");
-        socket
-            .write_all("命令格式不符合！".as_bytes())
-            .await
-            .unwrap();
-        return;
+pub async fn handle_zrange_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    if parts.len() > 5 {
+        return Frame::error("ERR syntax error");
     }
-    let key = parts[1].trim_end_matches('\0').to_string();
-    let start = parts[2].trim_end_matches('\0').parse::<i32>().unwrap();
-    let end = parts[3].trim_end_matches('\0').parse::<i32>().unwrap();
-    let mut map = hash_table.lock().await;
+    let with_scores = match parts.get(4) {
+        Some(option) if option.eq_ignore_ascii_case(b"withscores") => true,
+        Some(_) => return Frame::error("ERR syntax error"),
+        None => false,
+    };
+    let (Some(start), Some(stop)) = (
+        parse_number::<i64>(&parts[2]),
+        parse_number::<i64>(&parts[3]),
+    ) else {
+        return not_integer();
+    };
+    let db = client.storage.db.lock().await;
+    let set = match db.get_as::<SortedSet>(&parts[1]) {
+        Ok(Some(zset)) => &zset.skiplist,
+        Ok(None) => return Frame::Array(vec![]),
+        Err(err) => return err,
+    };
+    let Some((start, stop)) = normalize_range(start, stop, set.len()) else {
+        return Frame::Array(vec![]);
+    };
 
-    if start == 0 && end == -1 {
-        let mut res = String::new();
-        if let Some(set) = map.get_mut(&key) {
-            for value in set.iter() {
-                let message = format!("元素是： {}， 分数是： {}\n", value.1, value.0);
-                res.push_str(&message);
-            }
+    // 先按下标找到第一个元素，再从它开始顺序遍历
+    let mut res = Vec::new();
+    let first = Bound::Included(&set[start]);
+    for (score, member) in set.range(first, Bound::Unbounded).take(stop - start + 1) {
+        res.push(Frame::Bulk(member.clone()));
+        if with_scores {
+            res.push(Frame::Bulk(format_float(*score).into_bytes()));
         }
-        socket.write_all(res.as_bytes()).await.unwrap();
-        return;
     }
+    Frame::Array(res)
+}
 
-    if let Some(set) = map.get_mut(&key) {
-        let mut res = String::new();
-        for i in start..=end {
-            if let Some(value) = set.get(i as usize) {
-                let message = format!("元素是： {}， 分数是： {}\n", value.1, value.0);
-                res.push_str(&message);
-            }
-        }
-        socket.write_all(res.as_bytes()).await.unwrap();
-    } else {
-        error!("未找到key");
+// 处理 ZREM 命令，返回被删除的成员数量
+pub async fn handle_zrem_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let key = &parts[1];
+    let member = &parts[2];
+
+    let mut db = client.storage.db.lock().await;
+    let zset = match db.get_mut_as::<SortedSet>(key) {
+        Ok(Some(zset)) => zset,
+        Ok(None) => return Frame::Integer(0),
+        Err(err) => return err,
+    };
+    let removed = zset.remove(member);
+    // 有序集合为空时删除key
+    if zset.is_empty() {
+        db.remove(key);
     }
+    Frame::Integer(removed as i64)
 }
 
-// 处理 ZREM 命令
-pub async fn handle_zrem_command(
-    parts: Vec<&str>,
-    socket: &mut tokio::net::TcpStream,
-    hash_table: Arc<Mutex<HashMap<String, OrderedSkipList<(f64, String)>>>>,
-    hash: Arc<Mutex<HashMap<String, HashMap<String, f64>>>>,
-) {
-    if parts.len() != 3 {
-        error!("命令格式不符合！");
-        socket
-            .write_all("命令格式不符合！".as_bytes())
-            .await
-            .unwrap();
-        return;
+// 处理 ZSCORE 命令
+pub async fn handle_zscore_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let db = client.storage.db.lock().await;
+    match db.get_as::<SortedSet>(&parts[1]) {
+        Ok(zset) => match zset.and_then(|zset| zset.score(&parts[2])) {
+            Some(score) => Frame::Double(score),
+            None => Frame::Null,
+        },
+        Err(err) => err,
     }
+}
 
-    let key = parts[1].trim_end_matches('\0').to_string();
-    let member = parts[2].trim_end_matches('\0').to_string();
-
-    let mut map = hash_table.lock().await;
-    let mut hash_map = hash.lock().await;
-
-    if let Some(inner_map) = hash_map.get_mut(&key) {
-        if let Some(score) = inner_map.remove(&member) {
-            if let Some(set) = map.get_mut(&key) {
-                set.remove(&(score, member.clone()));
-            }
-            socket.write_all("ok".as_bytes()).await.unwrap();
-            return;
-        } else {
-            error!("未找到member");
-            socket.write_all("未找到member".as_bytes()).await.unwrap();
+// 处理 ZSCAN 命令，返回 [游标, [member, score, ...]]
+pub async fn handle_zscan_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let options = match ScanOptions::parse(&parts[2..], false) {
+        Ok(options) => options,
+        Err(err) => return err,
+    };
+    let db = client.storage.db.lock().await;
+    let zset = match db.get_as::<SortedSet>(&parts[1]) {
+        Ok(Some(zset)) => zset,
+        Ok(None) => return scan_reply(0, vec![]),
+        Err(err) => return err,
+    };
+    let (cursor, members) = zset.scan(options.cursor, options.count);
+    let mut res = Vec::new();
+    for (member, score) in members {
+        if options.matches(member) {
+            res.push(Frame::Bulk(member.clone()));
+            res.push(Frame::Bulk(format_float(score).into_bytes()));
         }
-    } else {
-        error!("未找到key");
-        socket
-            .write_all("未找到key或member".as_bytes())
-            .await
-            .unwrap();
     }
+    scan_reply(cursor, res)
 }
 
-// 处理 ZSCORE 命令
-pub async fn handle_zscore_command(
-    parts: Vec<&str>,
-    socket: &mut tokio::net::TcpStream,
-    hash_table: Arc<Mutex<HashMap<String, HashMap<String, f64>>>>,
-) {
-    if parts.len() != 3 {
-        error!("命令格式不符合！");
-        socket
-            .write_all("命令格式不符合！".as_bytes())
-            .await
-            .unwrap();
-        return;
-    }
-    let key = parts[1].trim_end_matches('\0').to_string();
-    let member = parts[2].trim_end_matches('\0').to_string();
-    let mut map = hash_table.lock().await;
-    if let Some(inner_map) = map.get_mut(&key) {
-        if let Some(score) = inner_map.get(&member) {
-            let message = format!("{}", score);
-            socket.write_all(message.as_bytes()).await.unwrap();
-            return;
-        } else {
-            error!("未找到member");
-            socket.write_all("未找到member".as_bytes()).await.unwrap();
+#[cfg(test)]
+mod tests {
+    use crate::operations::testing::{bulks, client, run};
+
+    #[tokio::test]
+    async fn zrange_follows_redis_index_rules() {
+        let mut client = client();
+        run(&mut client, &["zadd", "z", "3", "c", "1", "a", "2", "b"]).await;
+        let cases: [(&str, &str, &[&str]); 6] = [
+            ("0", "-1", &["a", "b", "c"]),
+            ("-2", "-1", &["b", "c"]),
+            ("1", "100", &["b", "c"]),
+            ("2", "1", &[]),
+            ("3", "10", &[]),
+            (
+                "-9223372036854775808",
+                "9223372036854775807",
+                &["a", "b", "c"],
+            ),
+        ];
+        for (start, stop, expected) in cases {
+            assert_eq!(
+                run(&mut client, &["zrange", "z", start, stop]).await,
+                bulks(expected)
+            );
         }
-    } else {
-        error!("未找到key");
-        socket.write_all("未找到key".as_bytes()).await.unwrap();
+        assert_eq!(
+            run(&mut client, &["zrange", "z", "-1", "-1", "withscores"]).await,
+            bulks(&["c", "3"])
+        );
+        assert_eq!(
+            run(&mut client, &["zrange", "none", "0", "-1"]).await,
+            bulks(&[])
+        );
     }
 }
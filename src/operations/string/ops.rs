@@ -1,96 +1,1199 @@
-use log::error;
-use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::io::AsyncWriteExt;
-use tokio::sync::Mutex;
+use crate::command::spec::CommandFlag::{Fast, ReadOnly, Write};
+use crate::command::spec::{CommandGroup, CommandSpec};
+use crate::command::table::CommandTable;
+use crate::connection::client::Client;
+use crate::db::storage::{Db, now_millis};
+use crate::db::value::Value;
+use crate::operations::utils::{
+    format_float, not_float, not_integer, parse_float, parse_integer, parse_number, wrong_args,
+};
+use crate::protocol::decoder::MAX_BULK_LEN;
+use crate::protocol::frame::Frame;
 
-// 检查命令格式
-fn check_command_format(parts: &[&str], expected_len: usize) -> bool {
-    parts.len() == expected_len
+// 注册 string 类型的命令
+pub fn register(table: &mut CommandTable) {
+    table.register(
+        CommandSpec::new("set", -3, &[Write], handle_set_command)
+            .keys(1, 1, 1)
+            .syntax("key value [NX | XX] [GET] [EX seconds | PX milliseconds | EXAT unix-time-seconds | PXAT unix-time-milliseconds | KEEPTTL]")
+            .doc(
+                CommandGroup::String,
+                "1.0.0",
+                "O(1)",
+                "Sets the string value of a key, ignoring its type. The key is created if it doesn't exist.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("setnx", 3, &[Write, Fast], handle_setnx_command)
+            .keys(1, 1, 1)
+            .syntax("key value")
+            .doc(
+                CommandGroup::String,
+                "1.0.0",
+                "O(1)",
+                "Set the string value of a key only when the key doesn't exist.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("setex", 4, &[Write], handle_setex_command)
+            .keys(1, 1, 1)
+            .syntax("key seconds value")
+            .doc(
+                CommandGroup::String,
+                "2.0.0",
+                "O(1)",
+                "Sets the string value and expiration time of a key. Creates the key if it doesn't exist.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("psetex", 4, &[Write], handle_psetex_command)
+            .keys(1, 1, 1)
+            .syntax("key milliseconds value")
+            .doc(
+                CommandGroup::String,
+                "2.6.0",
+                "O(1)",
+                "Sets both string value and expiration time in milliseconds of a key. The key is created if it doesn't exist.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("getset", 3, &[Write, Fast], handle_getset_command)
+            .keys(1, 1, 1)
+            .syntax("key value")
+            .doc(
+                CommandGroup::String,
+                "1.0.0",
+                "O(1)",
+                "Returns the previous string value of a key after setting it to a new value.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("getdel", 2, &[Write, Fast], handle_getdel_command)
+            .keys(1, 1, 1)
+            .syntax("key")
+            .doc(
+                CommandGroup::String,
+                "6.2.0",
+                "O(1)",
+                "Returns the string value of a key after deleting the key.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("getex", -2, &[Write, Fast], handle_getex_command)
+            .keys(1, 1, 1)
+            .syntax("key [EX seconds | PX milliseconds | EXAT unix-time-seconds | PXAT unix-time-milliseconds | PERSIST]")
+            .doc(
+                CommandGroup::String,
+                "6.2.0",
+                "O(1)",
+                "Returns the string value of a key after setting its expiration time.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("get", 2, &[ReadOnly, Fast], handle_get_command)
+            .keys(1, 1, 1)
+            .syntax("key")
+            .doc(
+                CommandGroup::String,
+                "1.0.0",
+                "O(1)",
+                "Returns the string value of a key.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("incr", 2, &[Write, Fast], handle_incr_command)
+            .keys(1, 1, 1)
+            .syntax("key")
+            .doc(
+                CommandGroup::String,
+                "1.0.0",
+                "O(1)",
+                "Increments the integer value of a key by one. Uses 0 as initial value if the key doesn't exist.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("decr", 2, &[Write, Fast], handle_decr_command)
+            .keys(1, 1, 1)
+            .syntax("key")
+            .doc(
+                CommandGroup::String,
+                "1.0.0",
+                "O(1)",
+                "Decrements the integer value of a key by one. Uses 0 as initial value if the key doesn't exist.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("incrby", 3, &[Write, Fast], handle_incrby_command)
+            .keys(1, 1, 1)
+            .syntax("key increment")
+            .doc(
+                CommandGroup::String,
+                "1.0.0",
+                "O(1)",
+                "Increments the integer value of a key by a number. Uses 0 as initial value if the key doesn't exist.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("decrby", 3, &[Write, Fast], handle_decrby_command)
+            .keys(1, 1, 1)
+            .syntax("key decrement")
+            .doc(
+                CommandGroup::String,
+                "1.0.0",
+                "O(1)",
+                "Decrements a number from the integer value of a key. Uses 0 as initial value if the key doesn't exist.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("incrbyfloat", 3, &[Write, Fast], handle_incrbyfloat_command)
+            .keys(1, 1, 1)
+            .syntax("key increment")
+            .doc(
+                CommandGroup::String,
+                "2.6.0",
+                "O(1)",
+                "Increment the floating point value of a key by a number. Uses 0 as initial value if the key doesn't exist.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("append", 3, &[Write, Fast], handle_append_command)
+            .keys(1, 1, 1)
+            .syntax("key value")
+            .doc(
+                CommandGroup::String,
+                "2.0.0",
+                "O(1). The amortized time complexity is O(1) assuming the appended value is small and the already present value is of any size, since the dynamic string library used by Redis will double the free space available on every reallocation.",
+                "Appends a string to the value of a key. Creates the key if it doesn't exist.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("strlen", 2, &[ReadOnly, Fast], handle_strlen_command)
+            .keys(1, 1, 1)
+            .syntax("key")
+            .doc(
+                CommandGroup::String,
+                "2.2.0",
+                "O(1)",
+                "Returns the length of a string value.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("getrange", 4, &[ReadOnly], handle_getrange_command)
+            .keys(1, 1, 1)
+            .syntax("key start end")
+            .doc(
+                CommandGroup::String,
+                "2.4.0",
+                "O(N) where N is the length of the returned string. The complexity is ultimately determined by the returned length, but because creating a substring from an existing string is very cheap, it can be considered O(1) for small strings.",
+                "Returns a substring of the string stored at a key.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("substr", 4, &[ReadOnly], handle_getrange_command)
+            .keys(1, 1, 1)
+            .syntax("key start end")
+            .doc(
+                CommandGroup::String,
+                "1.0.0",
+                "O(N) where N is the length of the returned string. The complexity is ultimately determined by the returned length, but because creating a substring from an existing string is very cheap, it can be considered O(1) for small strings.",
+                "Returns a substring from a string value.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("setrange", 4, &[Write], handle_setrange_command)
+            .keys(1, 1, 1)
+            .syntax("key offset value")
+            .doc(
+                CommandGroup::String,
+                "2.2.0",
+                "O(1), not counting the time taken to copy the new string in place. Usually, this string is very small so the amortized complexity is O(1). Otherwise, complexity is O(M) with M being the length of the value argument.",
+                "Overwrites a part of a string value with another by an offset. Creates the key if it doesn't exist.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("lcs", -3, &[ReadOnly], handle_lcs_command)
+            .keys(1, 2, 1)
+            .syntax("key1 key2 [LEN] [IDX] [MINMATCHLEN min-match-len] [WITHMATCHLEN]")
+            .doc(
+                CommandGroup::String,
+                "7.0.0",
+                "O(N*M) where N and M are the lengths of s1 and s2, respectively",
+                "Finds the longest common substring.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("mget", -2, &[ReadOnly, Fast], handle_mget_command)
+            .keys(1, -1, 1)
+            .syntax("key [key ...]")
+            .doc(
+                CommandGroup::String,
+                "1.0.0",
+                "O(N) where N is the number of keys to retrieve.",
+                "Atomically returns the string values of one or more keys.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("mset", -3, &[Write], handle_mset_command)
+            .keys(1, -1, 2)
+            .syntax("key value [key value ...]")
+            .doc(
+                CommandGroup::String,
+                "1.0.1",
+                "O(N) where N is the number of keys to set.",
+                "Atomically creates or modifies the string values of one or more keys.",
+            ),
+    );
+    table.register(
+        CommandSpec::new("msetnx", -3, &[Write], handle_msetnx_command)
+            .keys(1, -1, 2)
+            .syntax("key value [key value ...]")
+            .doc(
+                CommandGroup::String,
+                "1.0.1",
+                "O(N) where N is the number of keys to set.",
+                "Atomically modifies the string values of one or more keys only when all keys don't exist.",
+            ),
+    );
 }
 
-// 获取键对应的值
-async fn get_value(
-    parts: &[&str],
-    socket: &mut tokio::net::TcpStream,
-    hash_table: Arc<Mutex<HashMap<String, String>>>,
-) -> Option<String> {
-    let key = parts[1].trim_end_matches('\0').to_string();
-    let lock_hash = hash_table.lock().await;
-    match lock_hash.get(&key) {
-        Some(v) => Some(v.clone()),
-        None => {
-            error!("未找到key");
-            socket.write_all("未找到key".as_bytes()).await.unwrap();
-            None
+// SET 命令的 NX | XX 条件
+#[derive(PartialEq)]
+enum SetCondition {
+    Always,
+    // 只在 key 不存在时设置
+    Nx,
+    // 只在 key 已存在时设置
+    Xx,
+}
+
+// 过期时间选项
+#[derive(PartialEq)]
+enum Expiry {
+    // 清除原来的过期时间
+    None,
+    // 在指定的时刻（Unix 毫秒时间戳）过期
+    At(u64),
+    // 保留原来的过期时间
+    Keep,
+}
+
+// 解析 EX、PX、EXAT、PXAT 选项的参数，返回过期时刻，option 必须是小写的选项名
+fn parse_expire_option(option: &str, value: &[u8], command: &str) -> Result<u64, Frame> {
+    let Some(value) = parse_number::<i64>(value) else {
+        return Err(not_integer());
+    };
+    let invalid = || Frame::error(format!("ERR invalid expire time in '{}' command", command));
+    if value <= 0 {
+        return Err(invalid());
+    }
+    let at = match option {
+        "ex" => value
+            .checked_mul(1000)
+            .and_then(|ms| ms.checked_add(now_millis() as i64)),
+        "px" => value.checked_add(now_millis() as i64),
+        "exat" => value.checked_mul(1000),
+        _ => Some(value),
+    };
+    at.map(|at| at as u64).ok_or_else(invalid)
+}
+
+// 写入字符串值并按 expiry 处理过期时间
+fn set_string(db: &mut Db, key: &[u8], value: Vec<u8>, expiry: Expiry) {
+    let keep = match expiry {
+        Expiry::Keep => db.expire_time(key),
+        _ => None,
+    };
+    db.insert(key.to_vec(), Value::String(value));
+    match expiry {
+        Expiry::At(at) => {
+            db.set_expire(key, at);
+        }
+        Expiry::Keep => {
+            if let Some(at) = keep {
+                db.set_expire(key, at);
+            }
+        }
+        Expiry::None => {}
+    }
+}
+
+// 处理 SET 命令：SET key value [NX | XX] [GET] [EX | PX | EXAT | PXAT | KEEPTTL]
+// 不带 GET 时成功返回 OK，条件不满足返回空值；带 GET 时返回旧值
+pub async fn handle_set_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let mut condition = SetCondition::Always;
+    let mut get = false;
+    let mut expiry = Expiry::None;
+    let mut i = 3;
+    while i < parts.len() {
+        let option = String::from_utf8_lossy(&parts[i]).to_lowercase();
+        match option.as_str() {
+            "nx" if condition == SetCondition::Always => condition = SetCondition::Nx,
+            "xx" if condition == SetCondition::Always => condition = SetCondition::Xx,
+            "get" => get = true,
+            "keepttl" if expiry == Expiry::None => expiry = Expiry::Keep,
+            "ex" | "px" | "exat" | "pxat" if expiry == Expiry::None && i + 1 < parts.len() => {
+                i += 1;
+                match parse_expire_option(&option, &parts[i], "set") {
+                    Ok(at) => expiry = Expiry::At(at),
+                    Err(err) => return err,
+                }
+            }
+            _ => return Frame::error("ERR syntax error"),
+        }
+        i += 1;
+    }
+
+    let key = &parts[1];
+    let mut db = client.storage.db.lock().await;
+    // 带 GET 时旧值必须是字符串
+    let old = match db.get_as::<Vec<u8>>(key) {
+        Ok(old) => old.cloned(),
+        Err(err) if get => return err,
+        Err(_) => None,
+    };
+    let exists = db.contains_key(key);
+    let allowed = match condition {
+        SetCondition::Always => true,
+        SetCondition::Nx => !exists,
+        SetCondition::Xx => exists,
+    };
+    if allowed {
+        set_string(&mut db, key, parts[2].clone(), expiry);
+    }
+
+    if get {
+        match old {
+            Some(old) => Frame::Bulk(old),
+            None => Frame::Null,
         }
+    } else if allowed {
+        Frame::ok()
+    } else {
+        Frame::Null
     }
 }
 
-// 处理 SET 命令
-pub async fn handle_set_command(
-    parts: Vec<&str>,
-    socket: &mut tokio::net::TcpStream,
-    hash_table: Arc<Mutex<HashMap<String, String>>>,
-) {
-    if !check_command_format(&parts, 3) {
-        error!("命令格式不符合！");
-        socket
-            .write_all("命令格式不符合！".as_bytes())
-            .await
-            .unwrap();
-        return;
+// 处理 SETNX 命令，返回是否设置成功
+pub async fn handle_setnx_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let mut db = client.storage.db.lock().await;
+    if db.contains_key(&parts[1]) {
+        return Frame::Integer(0);
     }
+    set_string(&mut db, &parts[1], parts[2].clone(), Expiry::None);
+    Frame::Integer(1)
+}
 
-    let key = parts[1].trim_end_matches('\0').to_string();
-    let value = parts[2].trim_end_matches('\0').to_string();
+// SETEX 和 PSETEX 的公共实现
+async fn setex_generic(
+    parts: &[Vec<u8>],
+    client: &mut Client,
+    option: &str,
+    command: &str,
+) -> Frame {
+    let at = match parse_expire_option(option, &parts[2], command) {
+        Ok(at) => at,
+        Err(err) => return err,
+    };
+    let mut db = client.storage.db.lock().await;
+    set_string(&mut db, &parts[1], parts[3].clone(), Expiry::At(at));
+    Frame::ok()
+}
+
+// 处理 SETEX 命令
+pub async fn handle_setex_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    setex_generic(&parts, client, "ex", "setex").await
+}
 
-    hash_table.lock().await.insert(key, value);
+// 处理 PSETEX 命令
+pub async fn handle_psetex_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    setex_generic(&parts, client, "px", "psetex").await
+}
+
+// 处理 GETSET 命令，设置新值并返回旧值，同时清除过期时间
+pub async fn handle_getset_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let mut db = client.storage.db.lock().await;
+    let old = match db.get_as::<Vec<u8>>(&parts[1]) {
+        Ok(old) => old.cloned(),
+        Err(err) => return err,
+    };
+    set_string(&mut db, &parts[1], parts[2].clone(), Expiry::None);
+    match old {
+        Some(old) => Frame::Bulk(old),
+        None => Frame::Null,
+    }
+}
 
-    socket.write_all("ok".as_bytes()).await.unwrap();
+// 处理 GETDEL 命令，返回值并删除 key
+pub async fn handle_getdel_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let mut db = client.storage.db.lock().await;
+    if let Err(err) = db.get_as::<Vec<u8>>(&parts[1]) {
+        return err;
+    }
+    match db.remove(&parts[1]) {
+        Some(Value::String(value)) => Frame::Bulk(value),
+        _ => Frame::Null,
+    }
+}
+
+// 处理 GETEX 命令：GETEX key [EX | PX | EXAT | PXAT | PERSIST]，返回值并修改过期时间
+pub async fn handle_getex_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    // None 表示不修改过期时间，Some(None) 表示清除过期时间
+    let mut expiry: Option<Option<u64>> = None;
+    let mut i = 2;
+    while i < parts.len() {
+        let option = String::from_utf8_lossy(&parts[i]).to_lowercase();
+        match option.as_str() {
+            "persist" if expiry.is_none() => expiry = Some(None),
+            "ex" | "px" | "exat" | "pxat" if expiry.is_none() && i + 1 < parts.len() => {
+                i += 1;
+                match parse_expire_option(&option, &parts[i], "getex") {
+                    Ok(at) => expiry = Some(Some(at)),
+                    Err(err) => return err,
+                }
+            }
+            _ => return Frame::error("ERR syntax error"),
+        }
+        i += 1;
+    }
+
+    let mut db = client.storage.db.lock().await;
+    let value = match db.get_as::<Vec<u8>>(&parts[1]) {
+        Ok(Some(value)) => value.clone(),
+        Ok(None) => return Frame::Null,
+        Err(err) => return err,
+    };
+    match expiry {
+        // 过期时刻已经过去，直接删除
+        Some(Some(at)) if at <= now_millis() => {
+            db.remove(&parts[1]);
+        }
+        Some(Some(at)) => {
+            db.set_expire(&parts[1], at);
+        }
+        Some(None) => {
+            db.persist(&parts[1]);
+        }
+        None => {}
+    }
+    Frame::Bulk(value)
 }
 
 // 处理 GET 命令
-pub async fn handle_get_command(
-    parts: Vec<&str>,
-    socket: &mut tokio::net::TcpStream,
-    hash_table: Arc<Mutex<HashMap<String, String>>>,
-) {
-    if !check_command_format(&parts, 2) {
-        error!("命令格式不符合！");
-        socket
-            .write_all("命令格式不符合！".as_bytes())
-            .await
-            .unwrap();
-        return;
-    }
-
-    if let Some(value) = get_value(&parts, socket, hash_table.clone()).await {
-        socket.write_all(value.as_bytes()).await.unwrap();
-    }
-}
-
-// 处理 DEL 命令
-pub async fn handle_del_command(
-    parts: Vec<&str>,
-    socket: &mut tokio::net::TcpStream,
-    hash_table: Arc<Mutex<HashMap<String, String>>>,
-) {
-    if !check_command_format(&parts, 2) {
-        error!("命令格式不符合！");
-        socket
-            .write_all("命令格式不符合！".as_bytes())
-            .await
-            .unwrap();
-        return;
-    }
-
-    let key = parts[1].trim_end_matches('\0').to_string();
-    let mut lock_hash = hash_table.lock().await;
-    if let Some(value) = lock_hash.remove(&key) {
-        socket.write_all(value.as_bytes()).await.unwrap();
+pub async fn handle_get_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let db = client.storage.db.lock().await;
+    match db.get_as::<Vec<u8>>(&parts[1]) {
+        Ok(Some(value)) => Frame::Bulk(value.clone()),
+        Ok(None) => Frame::Null,
+        Err(err) => err,
+    }
+}
+
+// 原地更新字符串值，不影响 key 的过期时间
+fn replace_string(db: &mut Db, key: &[u8], value: Vec<u8>) {
+    match db.get_mut_as::<Vec<u8>>(key) {
+        Ok(Some(old)) => *old = value,
+        _ => {
+            db.insert(key.to_vec(), Value::String(value));
+        }
+    }
+}
+
+// INCR、DECR、INCRBY、DECRBY 的公共实现，key 不存在时视为 0
+async fn incr_generic(key: &[u8], client: &mut Client, increment: i64) -> Frame {
+    let mut db = client.storage.db.lock().await;
+    let current = match db.get_as::<Vec<u8>>(key) {
+        Ok(Some(value)) => match parse_integer(value) {
+            Some(current) => current,
+            None => return not_integer(),
+        },
+        Ok(None) => 0,
+        Err(err) => return err,
+    };
+    let Some(value) = current.checked_add(increment) else {
+        return Frame::error("ERR increment or decrement would overflow");
+    };
+    replace_string(&mut db, key, value.to_string().into_bytes());
+    Frame::Integer(value)
+}
+
+// 处理 INCR 命令
+pub async fn handle_incr_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    incr_generic(&parts[1], client, 1).await
+}
+
+// 处理 DECR 命令
+pub async fn handle_decr_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    incr_generic(&parts[1], client, -1).await
+}
+
+// 处理 INCRBY 命令
+pub async fn handle_incrby_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let Some(increment) = parse_integer(&parts[2]) else {
+        return not_integer();
+    };
+    incr_generic(&parts[1], client, increment).await
+}
+
+// 处理 DECRBY 命令
+pub async fn handle_decrby_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let Some(decrement) = parse_integer(&parts[2]) else {
+        return not_integer();
+    };
+    // i64::MIN 取反会溢出
+    let Some(increment) = decrement.checked_neg() else {
+        return Frame::error("ERR decrement would overflow");
+    };
+    incr_generic(&parts[1], client, increment).await
+}
+
+// 处理 INCRBYFLOAT 命令，返回并保存格式化后的结果
+pub async fn handle_incrbyfloat_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let Some(increment) = parse_float(&parts[2]) else {
+        return not_float();
+    };
+    let key = &parts[1];
+    let mut db = client.storage.db.lock().await;
+    let current = match db.get_as::<Vec<u8>>(key) {
+        Ok(Some(value)) => match parse_float(value) {
+            Some(current) => current,
+            None => return not_float(),
+        },
+        Ok(None) => 0.0,
+        Err(err) => return err,
+    };
+    let value = current + increment;
+    if !value.is_finite() {
+        return Frame::error("ERR increment would produce NaN or Infinity");
+    }
+    let value = format_float(value).into_bytes();
+    replace_string(&mut db, key, value.clone());
+    Frame::Bulk(value)
+}
+
+// 字符串超过最大长度时的错误回复
+fn string_too_long() -> Frame {
+    Frame::error("ERR string exceeds maximum allowed size (proto-max-bulk-len)")
+}
+
+// 处理 APPEND 命令，返回追加后的长度
+pub async fn handle_append_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let mut db = client.storage.db.lock().await;
+    // 先检查长度，避免超长时留下一个新建的空 key
+    let current = match db.get_as::<Vec<u8>>(&parts[1]) {
+        Ok(value) => value.map_or(0, |value| value.len()),
+        Err(err) => return err,
+    };
+    if (current + parts[2].len()) as i64 > MAX_BULK_LEN {
+        return string_too_long();
+    }
+    let Ok(value) = db.get_or_default::<Vec<u8>>(&parts[1]) else {
+        return Frame::Null;
+    };
+    value.extend_from_slice(&parts[2]);
+    Frame::Integer(value.len() as i64)
+}
+
+// 处理 STRLEN 命令，key 不存在时返回 0
+pub async fn handle_strlen_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let db = client.storage.db.lock().await;
+    match db.get_as::<Vec<u8>>(&parts[1]) {
+        Ok(value) => Frame::Integer(value.map_or(0, |value| value.len()) as i64),
+        Err(err) => err,
+    }
+}
+
+// 处理 GETRANGE 和 SUBSTR 命令，负数下标从末尾开始计算，超出范围的部分被截断
+pub async fn handle_getrange_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let (Some(start), Some(end)) = (
+        parse_number::<i64>(&parts[2]),
+        parse_number::<i64>(&parts[3]),
+    ) else {
+        return not_integer();
+    };
+    let db = client.storage.db.lock().await;
+    let value = match db.get_as::<Vec<u8>>(&parts[1]) {
+        Ok(Some(value)) => value,
+        Ok(None) => return Frame::Bulk(vec![]),
+        Err(err) => return err,
+    };
+    let len = value.len() as i64;
+    // 两个下标都是负数且 start 在 end 之后时直接返回空串
+    if len == 0 || (start < 0 && end < 0 && start > end) {
+        return Frame::Bulk(vec![]);
+    }
+    let start = if start < 0 {
+        (len + start).max(0)
     } else {
-        error!("未找到key");
-        socket.write_all("未找到key".as_bytes()).await.unwrap();
+        start
+    };
+    let end = if end < 0 {
+        (len + end).max(0)
+    } else {
+        end.min(len - 1)
+    };
+    if start > end {
+        return Frame::Bulk(vec![]);
+    }
+    Frame::Bulk(value[start as usize..=end as usize].to_vec())
+}
+
+// 处理 SETRANGE 命令，从 offset 开始覆盖写入，原字符串不够长时用 0 字节填充，返回修改后的长度
+pub async fn handle_setrange_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let offset = match parse_number::<i64>(&parts[2]) {
+        Some(offset) if offset >= 0 => offset as usize,
+        Some(_) => return Frame::error("ERR offset is out of range"),
+        None => return not_integer(),
+    };
+    let patch = &parts[3];
+    let mut db = client.storage.db.lock().await;
+    // 写入空串不会创建 key
+    if patch.is_empty() {
+        return match db.get_as::<Vec<u8>>(&parts[1]) {
+            Ok(value) => Frame::Integer(value.map_or(0, |value| value.len()) as i64),
+            Err(err) => err,
+        };
+    }
+    if (offset + patch.len()) as i64 > MAX_BULK_LEN {
+        return string_too_long();
+    }
+    let value = match db.get_or_default::<Vec<u8>>(&parts[1]) {
+        Ok(value) => value,
+        Err(err) => return err,
+    };
+    let end = offset + patch.len();
+    if value.len() < end {
+        value.resize(end, 0);
+    }
+    value[offset..end].copy_from_slice(patch);
+    Frame::Integer(value.len() as i64)
+}
+
+// 处理 LCS 命令：LCS key1 key2 [LEN] [IDX] [MINMATCHLEN len] [WITHMATCHLEN]
+// 默认返回最长公共子序列本身，LEN 只返回长度，IDX 返回每一段匹配在两个字符串中的位置
+pub async fn handle_lcs_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let (mut get_len, mut get_idx, mut with_match_len) = (false, false, false);
+    let mut min_match_len = 0;
+    let mut i = 3;
+    while i < parts.len() {
+        let option = String::from_utf8_lossy(&parts[i]).to_lowercase();
+        match option.as_str() {
+            "len" => get_len = true,
+            "idx" => get_idx = true,
+            "withmatchlen" => with_match_len = true,
+            "minmatchlen" if i + 1 < parts.len() => {
+                i += 1;
+                match parse_number::<i64>(&parts[i]) {
+                    Some(len) => min_match_len = len.max(0) as usize,
+                    None => return not_integer(),
+                }
+            }
+            _ => return Frame::error("ERR syntax error"),
+        }
+        i += 1;
+    }
+    if get_len && get_idx {
+        return Frame::error("ERR If you want both the length and indexes, please just use IDX.");
+    }
+
+    let db = client.storage.db.lock().await;
+    let (Ok(a), Ok(b)) = (
+        db.get_as::<Vec<u8>>(&parts[1]),
+        db.get_as::<Vec<u8>>(&parts[2]),
+    ) else {
+        return Frame::error("ERR The specified keys must contain string values");
+    };
+    let (a, b) = (
+        a.map_or(&[][..], |a| a.as_slice()),
+        b.map_or(&[][..], |b| b.as_slice()),
+    );
+
+    // dp[i][j] 为 a[..i] 与 b[..j] 的最长公共子序列长度
+    let width = b.len() + 1;
+    let Some(cells) = (a.len() + 1).checked_mul(width).filter(|cells| {
+        cells
+            .checked_mul(size_of::<u32>())
+            .is_some_and(|size| size as i64 <= MAX_BULK_LEN)
+    }) else {
+        return Frame::error(
+            "ERR Insufficient memory, transient memory for LCS exceeds proto-max-bulk-len",
+        );
+    };
+    let mut dp = vec![0u32; cells];
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            dp[i * width + j] = if a[i - 1] == b[j - 1] {
+                dp[(i - 1) * width + j - 1] + 1
+            } else {
+                dp[(i - 1) * width + j].max(dp[i * width + j - 1])
+            };
+        }
+    }
+    let lcs_len = dp[a.len() * width + b.len()] as usize;
+    if get_len {
+        return Frame::Integer(lcs_len as i64);
+    }
+
+    // 从末尾回溯，同时记录连续匹配的区间
+    let mut result = vec![0u8; lcs_len];
+    let mut matches = Vec::new();
+    let (mut i, mut j, mut idx) = (a.len(), b.len(), lcs_len);
+    // 当前区间在 a 和 b 中的 [start, end]，a_range 为 None 表示还没有区间
+    let mut a_range: Option<(usize, usize)> = None;
+    let mut b_range = (0, 0);
+    while i > 0 && j > 0 {
+        let mut emit = false;
+        if a[i - 1] == b[j - 1] {
+            result[idx - 1] = a[i - 1];
+            match a_range {
+                None => {
+                    a_range = Some((i - 1, i - 1));
+                    b_range = (j - 1, j - 1);
+                }
+                // 与当前区间相邻，向前扩展
+                Some((start, end)) if start == i && b_range.0 == j => {
+                    a_range = Some((start - 1, end));
+                    b_range.0 -= 1;
+                }
+                Some(_) => emit = true,
+            }
+            // 匹配到了某个字符串的开头，循环即将结束
+            if a_range.is_some_and(|(start, _)| start == 0) || b_range.0 == 0 {
+                emit = true;
+            }
+            idx -= 1;
+            i -= 1;
+            j -= 1;
+        } else {
+            if dp[(i - 1) * width + j] > dp[i * width + j - 1] {
+                i -= 1;
+            } else {
+                j -= 1;
+            }
+            if a_range.is_some() {
+                emit = true;
+            }
+        }
+
+        if emit && let Some((a_start, a_end)) = a_range.take() {
+            let match_len = a_end - a_start + 1;
+            if get_idx && (min_match_len == 0 || match_len >= min_match_len) {
+                let mut item = vec![
+                    Frame::Array(vec![
+                        Frame::Integer(a_start as i64),
+                        Frame::Integer(a_end as i64),
+                    ]),
+                    Frame::Array(vec![
+                        Frame::Integer(b_range.0 as i64),
+                        Frame::Integer(b_range.1 as i64),
+                    ]),
+                ];
+                if with_match_len {
+                    item.push(Frame::Integer(match_len as i64));
+                }
+                matches.push(Frame::Array(item));
+            }
+        }
+    }
+
+    if get_idx {
+        Frame::Map(vec![
+            (Frame::Bulk(b"matches".to_vec()), Frame::Array(matches)),
+            (Frame::Bulk(b"len".to_vec()), Frame::Integer(lcs_len as i64)),
+        ])
+    } else {
+        Frame::Bulk(result)
+    }
+}
+
+// 处理 MGET 命令，不存在或不是字符串的 key 对应空值
+pub async fn handle_mget_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    let db = client.storage.db.lock().await;
+    Frame::Array(
+        parts[1..]
+            .iter()
+            .map(|key| match db.get_as::<Vec<u8>>(key) {
+                Ok(Some(value)) => Frame::Bulk(value.clone()),
+                _ => Frame::Null,
+            })
+            .collect(),
+    )
+}
+
+// 处理 MSET 命令，在一次加锁内写入所有 key
+pub async fn handle_mset_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    // key 和 value 必须成对出现
+    if parts.len().is_multiple_of(2) {
+        return wrong_args("mset");
+    }
+    let mut db = client.storage.db.lock().await;
+    for pair in parts[1..].chunks(2) {
+        set_string(&mut db, &pair[0], pair[1].clone(), Expiry::None);
+    }
+    Frame::ok()
+}
+
+// 处理 MSETNX 命令，只要有一个 key 已存在就什么都不写入，返回是否写入
+pub async fn handle_msetnx_command(parts: Vec<Vec<u8>>, client: &mut Client) -> Frame {
+    if parts.len().is_multiple_of(2) {
+        return wrong_args("msetnx");
+    }
+    let mut db = client.storage.db.lock().await;
+    if parts[1..].iter().step_by(2).any(|key| db.contains_key(key)) {
+        return Frame::Integer(0);
+    }
+    for pair in parts[1..].chunks(2) {
+        set_string(&mut db, &pair[0], pair[1].clone(), Expiry::None);
+    }
+    Frame::Integer(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::connection::client::Client;
+    use crate::db::storage::now_millis;
+    use crate::operations::testing::{bulk, client, run};
+    use crate::operations::utils::wrong_type;
+    use crate::protocol::frame::Frame;
+
+    #[tokio::test]
+    async fn incrbyfloat_keeps_full_precision() {
+        let mut client = client();
+        run(&mut client, &["set", "f", "1234567890123456"]).await;
+        assert_eq!(
+            run(&mut client, &["incrbyfloat", "f", "1"]).await,
+            bulk("1234567890123457")
+        );
+        assert_eq!(
+            run(&mut client, &["get", "f"]).await,
+            bulk("1234567890123457")
+        );
+        assert_eq!(
+            run(&mut client, &["incrbyfloat", "g", "0.12345678901234567"]).await,
+            bulk("0.12345678901234566")
+        );
+        run(&mut client, &["set", "h", "10.50"]).await;
+        assert_eq!(
+            run(&mut client, &["incrbyfloat", "h", "0.1"]).await,
+            bulk("10.6")
+        );
+        assert_eq!(
+            run(&mut client, &["incrbyfloat", "h", "-5.6"]).await,
+            bulk("5")
+        );
+        assert_eq!(
+            run(&mut client, &["incrbyfloat", "h", "5.0e3"]).await,
+            bulk("5005")
+        );
+        assert_eq!(
+            run(&mut client, &["incrbyfloat", "big", "1e300"]).await,
+            bulk("1e+300")
+        );
+        assert_eq!(
+            run(&mut client, &["incrbyfloat", "big", "1e300"]).await,
+            bulk("2e+300")
+        );
+    }
+
+    #[tokio::test]
+    async fn incrbyfloat_rejects_invalid_values() {
+        let mut client = client();
+        assert_eq!(
+            run(&mut client, &["incrbyfloat", "f", "abc"]).await,
+            Frame::error("ERR value is not a valid float")
+        );
+        run(&mut client, &["set", "f", "1.7e308"]).await;
+        assert_eq!(
+            run(&mut client, &["incrbyfloat", "f", "1.7e308"]).await,
+            Frame::error("ERR increment would produce NaN or Infinity")
+        );
+        assert_eq!(run(&mut client, &["get", "f"]).await, bulk("1.7e308"));
+    }
+
+    #[tokio::test]
+    async fn incr_detects_overflow() {
+        let mut client = client();
+        run(&mut client, &["set", "n", "9223372036854775806"]).await;
+        assert_eq!(
+            run(&mut client, &["incr", "n"]).await,
+            Frame::Integer(i64::MAX)
+        );
+        assert_eq!(
+            run(&mut client, &["incr", "n"]).await,
+            Frame::error("ERR increment or decrement would overflow")
+        );
+        assert_eq!(
+            run(&mut client, &["incrby", "n", "-9223372036854775808"]).await,
+            Frame::Integer(-1)
+        );
+        assert_eq!(
+            run(&mut client, &["incrby", "n", "-9223372036854775808"]).await,
+            Frame::error("ERR increment or decrement would overflow")
+        );
+        assert_eq!(
+            run(&mut client, &["decrby", "n", "-9223372036854775808"]).await,
+            Frame::error("ERR decrement would overflow")
+        );
+        assert_eq!(
+            run(&mut client, &["incrby", "n", "9223372036854775808"]).await,
+            Frame::error("ERR value is not an integer or out of range")
+        );
+        assert_eq!(run(&mut client, &["get", "n"]).await, bulk("-1"));
+    }
+
+    #[tokio::test]
+    async fn set_honours_nx_xx_and_get() {
+        let mut client = client();
+        assert_eq!(
+            run(&mut client, &["set", "k", "v1", "nx"]).await,
+            Frame::ok()
+        );
+        assert_eq!(
+            run(&mut client, &["set", "k", "v2", "nx"]).await,
+            Frame::Null
+        );
+        assert_eq!(run(&mut client, &["get", "k"]).await, bulk("v1"));
+        assert_eq!(
+            run(&mut client, &["set", "x", "v", "xx"]).await,
+            Frame::Null
+        );
+        assert_eq!(run(&mut client, &["exists", "x"]).await, Frame::Integer(0));
+        assert_eq!(
+            run(&mut client, &["set", "k", "v2", "xx"]).await,
+            Frame::ok()
+        );
+
+        // 带 GET 时返回旧值，条件不满足时同样返回旧值但不写入
+        assert_eq!(
+            run(&mut client, &["set", "k", "v3", "get"]).await,
+            bulk("v2")
+        );
+        assert_eq!(
+            run(&mut client, &["set", "k", "v4", "nx", "get"]).await,
+            bulk("v3")
+        );
+        assert_eq!(run(&mut client, &["get", "k"]).await, bulk("v3"));
+        assert_eq!(
+            run(&mut client, &["set", "new", "v", "get"]).await,
+            Frame::Null
+        );
+        assert_eq!(run(&mut client, &["get", "new"]).await, bulk("v"));
+
+        run(&mut client, &["rpush", "l", "a"]).await;
+        assert_eq!(
+            run(&mut client, &["set", "l", "v", "get"]).await,
+            wrong_type()
+        );
+        assert_eq!(run(&mut client, &["set", "l", "v"]).await, Frame::ok());
+        assert_eq!(run(&mut client, &["get", "l"]).await, bulk("v"));
+    }
+
+    #[tokio::test]
+    async fn set_expire_options() {
+        let mut client = client();
+        run(&mut client, &["set", "k", "v", "ex", "100"]).await;
+        assert_eq!(run(&mut client, &["ttl", "k"]).await, Frame::Integer(100));
+        run(&mut client, &["set", "k", "v", "px", "200000"]).await;
+        assert_eq!(run(&mut client, &["ttl", "k"]).await, Frame::Integer(200));
+
+        // KEEPTTL 保留原来的过期时间，不带过期选项时清除
+        run(&mut client, &["set", "k", "v2", "keepttl"]).await;
+        assert_eq!(run(&mut client, &["ttl", "k"]).await, Frame::Integer(200));
+        run(&mut client, &["set", "k", "v3"]).await;
+        assert_eq!(run(&mut client, &["ttl", "k"]).await, Frame::Integer(-1));
+
+        let now = now_millis();
+        let at = (now / 1000 + 300).to_string();
+        run(&mut client, &["set", "k", "v", "exat", &at]).await;
+        assert_eq!(
+            run(&mut client, &["expiretime", "k"]).await,
+            Frame::Integer(now as i64 / 1000 + 300)
+        );
+        let at = (now + 400_000).to_string();
+        run(&mut client, &["set", "k", "v", "pxat", &at]).await;
+        assert_eq!(
+            run(&mut client, &["pexpiretime", "k"]).await,
+            Frame::Integer(now as i64 + 400_000)
+        );
+
+        // 过期时刻已经过去时 key 立即不可见
+        assert_eq!(
+            run(&mut client, &["set", "k", "v", "pxat", "1"]).await,
+            Frame::ok()
+        );
+        assert_eq!(run(&mut client, &["exists", "k"]).await, Frame::Integer(0));
+    }
+
+    #[tokio::test]
+    async fn set_rejects_conflicting_and_invalid_options() {
+        let mut client = client();
+        let syntax_errors: [&[&str]; 6] = [
+            &["set", "k", "v", "nx", "xx"],
+            &["set", "k", "v", "ex", "10", "px", "10"],
+            &["set", "k", "v", "ex", "10", "keepttl"],
+            &["set", "k", "v", "keepttl", "exat", "10"],
+            &["set", "k", "v", "ex"],
+            &["set", "k", "v", "bogus"],
+        ];
+        for command in syntax_errors {
+            assert_eq!(
+                run(&mut client, command).await,
+                Frame::error("ERR syntax error")
+            );
+        }
+        for (option, value) in [("ex", "0"), ("px", "-1"), ("exat", "0"), ("pxat", "-5")] {
+            assert_eq!(
+                run(&mut client, &["set", "k", "v", option, value]).await,
+                Frame::error("ERR invalid expire time in 'set' command")
+            );
+        }
+        assert_eq!(
+            run(&mut client, &["set", "k", "v", "ex", "9223372036854775807"]).await,
+            Frame::error("ERR invalid expire time in 'set' command")
+        );
+        assert_eq!(
+            run(&mut client, &["set", "k", "v", "ex", "abc"]).await,
+            Frame::error("ERR value is not an integer or out of range")
+        );
+        assert_eq!(run(&mut client, &["exists", "k"]).await, Frame::Integer(0));
+    }
+
+    // LCS IDX 中的一段匹配：[[a_start, a_end], [b_start, b_end]] 以及可选的匹配长度
+    fn lcs_match(a: (i64, i64), b: (i64, i64), len: Option<i64>) -> Frame {
+        let mut item = vec![
+            Frame::Array(vec![Frame::Integer(a.0), Frame::Integer(a.1)]),
+            Frame::Array(vec![Frame::Integer(b.0), Frame::Integer(b.1)]),
+        ];
+        item.extend(len.map(Frame::Integer));
+        Frame::Array(item)
+    }
+
+    fn lcs_idx(matches: Vec<Frame>, len: i64) -> Frame {
+        Frame::Map(vec![
+            (bulk("matches"), Frame::Array(matches)),
+            (bulk("len"), Frame::Integer(len)),
+        ])
+    }
+
+    #[tokio::test]
+    async fn lcs_options() {
+        let mut client = client();
+        run(&mut client, &["mset", "a", "ohmytext", "b", "mynewtext"]).await;
+        assert_eq!(run(&mut client, &["lcs", "a", "b"]).await, bulk("mytext"));
+        assert_eq!(
+            run(&mut client, &["lcs", "a", "b", "len"]).await,
+            Frame::Integer(6)
+        );
+        assert_eq!(
+            run(&mut client, &["lcs", "a", "b", "idx"]).await,
+            lcs_idx(
+                vec![
+                    lcs_match((4, 7), (5, 8), None),
+                    lcs_match((2, 3), (0, 1), None),
+                ],
+                6
+            )
+        );
+        assert_eq!(
+            run(&mut client, &["lcs", "a", "b", "idx", "withmatchlen"]).await,
+            lcs_idx(
+                vec![
+                    lcs_match((4, 7), (5, 8), Some(4)),
+                    lcs_match((2, 3), (0, 1), Some(2)),
+                ],
+                6
+            )
+        );
+        assert_eq!(
+            run(
+                &mut client,
+                &["lcs", "a", "b", "idx", "minmatchlen", "4", "withmatchlen"]
+            )
+            .await,
+            lcs_idx(vec![lcs_match((4, 7), (5, 8), Some(4))], 6)
+        );
+    }
+
+    #[tokio::test]
+    async fn lcs_edge_cases_and_errors() {
+        let mut client = client();
+        run(&mut client, &["set", "a", "abc"]).await;
+        // 不存在的 key 视为空字符串
+        assert_eq!(run(&mut client, &["lcs", "a", "none"]).await, bulk(""));
+        assert_eq!(
+            run(&mut client, &["lcs", "a", "none", "idx"]).await,
+            lcs_idx(vec![], 0)
+        );
+        assert_eq!(
+            run(&mut client, &["lcs", "a", "a", "idx", "withmatchlen"]).await,
+            lcs_idx(vec![lcs_match((0, 2), (0, 2), Some(3))], 3)
+        );
+        assert_eq!(
+            run(&mut client, &["lcs", "a", "a", "len", "idx"]).await,
+            Frame::error("ERR If you want both the length and indexes, please just use IDX.")
+        );
+        assert_eq!(
+            run(&mut client, &["lcs", "a", "a", "minmatchlen"]).await,
+            Frame::error("ERR syntax error")
+        );
+        assert_eq!(
+            run(&mut client, &["lcs", "a", "a", "minmatchlen", "x"]).await,
+            Frame::error("ERR value is not an integer or out of range")
+        );
+        run(&mut client, &["rpush", "l", "x"]).await;
+        assert_eq!(
+            run(&mut client, &["lcs", "a", "l"]).await,
+            Frame::error("ERR The specified keys must contain string values")
+        );
+    }
+
+    #[tokio::test]
+    async fn msetnx_sets_all_keys_or_none() {
+        let mut client = client();
+        assert_eq!(
+            run(&mut client, &["msetnx", "a", "1", "b", "2"]).await,
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            run(&mut client, &["mget", "a", "b"]).await,
+            Frame::Array(vec![bulk("1"), bulk("2")])
+        );
+        // 只要有一个 key 已存在（无论类型），其余的 key 也不会写入
+        assert_eq!(
+            run(&mut client, &["msetnx", "c", "3", "b", "4"]).await,
+            Frame::Integer(0)
+        );
+        assert_eq!(run(&mut client, &["exists", "c"]).await, Frame::Integer(0));
+        assert_eq!(run(&mut client, &["get", "b"]).await, bulk("2"));
+        run(&mut client, &["rpush", "l", "x"]).await;
+        assert_eq!(
+            run(&mut client, &["msetnx", "c", "3", "l", "4"]).await,
+            Frame::Integer(0)
+        );
+        assert_eq!(run(&mut client, &["exists", "c"]).await, Frame::Integer(0));
+        assert_eq!(
+            run(&mut client, &["msetnx", "c", "3", "d"]).await,
+            Frame::error("ERR wrong number of arguments for 'msetnx' command")
+        );
+    }
+
+    #[tokio::test]
+    async fn concurrent_msetnx_on_overlapping_keys_lets_exactly_one_win() {
+        for _ in 0..20 {
+            let mut first = client();
+            let mut second = Client::new(first.storage.clone());
+            let (a, b) = tokio::join!(
+                run(&mut first, &["msetnx", "a", "1", "b", "1"]),
+                run(&mut second, &["msetnx", "b", "2", "c", "2"]),
+            );
+            let mut results = [a, b];
+            results.sort_by_key(|frame| matches!(frame, Frame::Integer(1)));
+            assert_eq!(results, [Frame::Integer(0), Frame::Integer(1)]);
+            // 失败的一方没有写入任何 key
+            let values = run(&mut first, &["mget", "a", "b", "c"]).await;
+            assert!(
+                values == Frame::Array(vec![bulk("1"), bulk("1"), Frame::Null])
+                    || values == Frame::Array(vec![Frame::Null, bulk("2"), bulk("2")])
+            );
+        }
     }
 }
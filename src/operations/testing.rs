@@ -0,0 +1,23 @@
+// 测试用的辅助函数，命令通过 execute_command 执行，与客户端请求经过的路径一致
+use crate::command::table::execute_command;
+use crate::connection::client::Client;
+use crate::db::storage::Storage;
+use crate::protocol::frame::Frame;
+
+// 使用全新键空间的客户端
+pub fn client() -> Client {
+    Client::new(Storage::new())
+}
+
+pub async fn run(client: &mut Client, args: &[&str]) -> Frame {
+    let parts = args.iter().map(|arg| arg.as_bytes().to_vec()).collect();
+    execute_command(parts, client).await
+}
+
+pub fn bulk(item: &str) -> Frame {
+    Frame::Bulk(item.as_bytes().to_vec())
+}
+
+pub fn bulks(items: &[&str]) -> Frame {
+    Frame::bulks(items.iter().map(|item| item.as_bytes().to_vec()))
+}
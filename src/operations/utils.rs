@@ -0,0 +1,128 @@
+use crate::protocol::frame::Frame;
+use std::str::FromStr;
+
+// 将二进制参数解析为数字，参数不是合法的 UTF-8 或数字时返回 None
+pub fn parse_number<T: FromStr>(data: &[u8]) -> Option<T> {
+    std::str::from_utf8(data).ok()?.parse::<T>().ok()
+}
+
+// 参数个数不正确时的错误回复
+pub fn wrong_args(command: &str) -> Frame {
+    Frame::error(format!(
+        "ERR wrong number of arguments for '{}' command",
+        command
+    ))
+}
+
+// 参数不是整数时的错误回复
+pub fn not_integer() -> Frame {
+    Frame::error("ERR value is not an integer or out of range")
+}
+
+// 解析浮点数，不接受 NaN
+pub fn parse_float(data: &[u8]) -> Option<f64> {
+    parse_number::<f64>(data).filter(|value| !value.is_nan())
+}
+
+// 参数不是浮点数时的错误回复
+pub fn not_float() -> Frame {
+    Frame::error("ERR value is not a valid float")
+}
+
+// 按 Redis 的方式格式化浮点数，使用能精确还原该值的最短数字，整数值不带小数部分，
+// 与 %.17g 一样，十进制指数小于 -4 或不小于 17 时使用指数形式，如 1e+300、1.5e-07
+pub fn format_float(value: f64) -> String {
+    if value.is_infinite() {
+        return if value > 0.0 { "inf" } else { "-inf" }.to_string();
+    }
+    let scientific = format!("{:e}", value);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let exponent = exponent.parse::<i32>().unwrap_or(0);
+    if (-4..17).contains(&exponent) {
+        return format!("{}", value);
+    }
+    let sign = if exponent < 0 { '-' } else { '+' };
+    format!("{}e{}{:02}", mantissa, sign, exponent.abs())
+}
+
+// 对类型不符的 key 执行命令时的错误回复
+pub fn wrong_type() -> Frame {
+    Frame::error("WRONGTYPE Operation against a key holding the wrong kind of value")
+}
+
+// 按 Redis 的规则严格解析 64 位整数，不接受前导 +、多余的前导 0 以及 -0
+pub fn parse_integer(data: &[u8]) -> Option<i64> {
+    match data {
+        [b'0'] => Some(0),
+        [b'+', ..] | [b'0', ..] | [b'-', b'0', ..] => None,
+        _ => parse_number::<i64>(data),
+    }
+}
+
+// 按 Redis 的规则把 [start, stop] 下标范围转换为长度为 len 的序列上的闭区间，
+// 负数下标从尾部开始计算，超出两端的部分被截掉，范围为空时返回 None
+pub fn normalize_range(start: i64, stop: i64, len: usize) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let start = if start < 0 { len + start } else { start }.max(0);
+    let stop = if stop < 0 { len + stop } else { stop }.min(len - 1);
+    (start <= stop).then_some((start as usize, stop as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_float_uses_shortest_digits() {
+        assert_eq!(format_float(0.0), "0");
+        assert_eq!(format_float(3.0), "3");
+        assert_eq!(format_float(-2.5), "-2.5");
+        assert_eq!(format_float(0.1), "0.1");
+        assert_eq!(format_float(0.0001), "0.0001");
+        assert_eq!(format_float(1234567890123457.0), "1234567890123457");
+        assert_eq!(format_float(1e16), "10000000000000000");
+        assert_eq!(format_float(f64::INFINITY), "inf");
+        assert_eq!(format_float(f64::NEG_INFINITY), "-inf");
+    }
+
+    #[test]
+    fn format_float_uses_exponent_for_extreme_magnitudes() {
+        assert_eq!(format_float(1e300), "1e+300");
+        assert_eq!(format_float(-1.5e17), "-1.5e+17");
+        assert_eq!(format_float(0.00001), "1e-05");
+        assert_eq!(format_float(1.25e-7), "1.25e-07");
+        assert_eq!(format_float(f64::MAX), "1.7976931348623157e+308");
+        assert_eq!(format_float(5e-324), "5e-324");
+    }
+
+    #[test]
+    fn normalize_range_counts_negative_indexes_from_the_end() {
+        assert_eq!(normalize_range(0, -1, 5), Some((0, 4)));
+        assert_eq!(normalize_range(-3, -2, 5), Some((2, 3)));
+        assert_eq!(normalize_range(-100, 1, 5), Some((0, 1)));
+        assert_eq!(normalize_range(-100, -6, 5), None);
+    }
+
+    #[test]
+    fn normalize_range_rejects_empty_ranges() {
+        assert_eq!(normalize_range(3, 1, 5), None);
+        assert_eq!(normalize_range(-1, -2, 5), None);
+        assert_eq!(normalize_range(5, 10, 5), None);
+        assert_eq!(normalize_range(0, -1, 0), None);
+        assert_eq!(normalize_range(0, 0, 0), None);
+    }
+
+    #[test]
+    fn normalize_range_clamps_stop_to_the_last_index() {
+        assert_eq!(normalize_range(2, 5, 5), Some((2, 4)));
+        assert_eq!(normalize_range(4, 100, 5), Some((4, 4)));
+    }
+
+    #[test]
+    fn normalize_range_handles_extreme_indexes() {
+        assert_eq!(normalize_range(i64::MIN, i64::MAX, 5), Some((0, 4)));
+        assert_eq!(normalize_range(i64::MIN, i64::MIN, 5), None);
+        assert_eq!(normalize_range(i64::MAX, i64::MAX, 5), None);
+        assert_eq!(normalize_range(0, i64::MAX, 0), None);
+    }
+}
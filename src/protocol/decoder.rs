@@ -0,0 +1,395 @@
+use crate::protocol::frame::Frame;
+use crate::protocol::inline::{MAX_INLINE_LEN, split_args};
+
+// 单个批量字符串的最大长度，与 Redis 的 proto-max-bulk-len 默认值一致
+pub const MAX_BULK_LEN: i64 = 512 * 1024 * 1024;
+// 单个数组的最大元素个数
+const MAX_ARRAY_LEN: i64 = 1024 * 1024;
+// 解析回复时数组的最大嵌套层数
+const MAX_NESTING: usize = 128;
+
+// 增量式 RESP2 解码器
+// 从 socket 读到的字节先追加到内部缓冲区，再尽可能多地从中解析出完整的帧，
+// 不完整的帧保留在缓冲区中等待下一次读取
+#[derive(Default)]
+pub struct Decoder {
+    buffer: Vec<u8>,
+    // 缓冲区中已解析部分的结束位置，流水线下一次读取可能解析出很多帧，
+    // 只记录位置而不是每解析一个帧就移动剩余数据
+    pos: usize,
+    // 正在解析的 RESP 数组命令，参数跨多次读取逐个到达时保留已解析的部分，
+    // 每次读取只需要处理新到达的数据
+    multibulk: Option<Multibulk>,
+}
+
+// 尚未解析完的 RESP 数组命令
+struct Multibulk {
+    // 还需要解析的参数个数
+    remaining: usize,
+    args: Vec<Vec<u8>>,
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Decoder::default()
+    }
+
+    // 追加新读取到的数据，顺便丢弃已经解析过的部分
+    pub fn extend(&mut self, data: &[u8]) {
+        if self.pos > 0 {
+            self.buffer.drain(..self.pos);
+            self.pos = 0;
+        }
+        self.buffer.extend_from_slice(data);
+    }
+
+    // 尝试解析下一个完整的帧，数据不足时返回 Ok(None)，用于解析服务端的回复
+    pub fn decode(&mut self) -> Result<Option<Frame>, String> {
+        match parse_frame(&self.buffer, self.pos, 0)? {
+            Some((frame, next)) => {
+                self.pos = next;
+                Ok(Some(frame))
+            }
+            None => Ok(None),
+        }
+    }
+
+    // 尝试解析下一条完整的命令，返回命令的参数列表
+    // 以 '*' 开头的按 RESP 数组解析，其余按内联命令（以换行结尾的一行文本）解析
+    pub fn next_command(&mut self) -> Result<Option<Vec<Vec<u8>>>, String> {
+        if self.multibulk.is_none() {
+            match self.buffer.get(self.pos) {
+                None => return Ok(None),
+                Some(b'*') => {
+                    if !self.start_multibulk()? {
+                        return Ok(None);
+                    }
+                }
+                Some(_) => return self.next_inline_command(),
+            }
+        }
+        self.next_multibulk_command()
+    }
+
+    // 解析数组的头部 *<count>\r\n，数据不足时返回 Ok(false)
+    fn start_multibulk(&mut self) -> Result<bool, String> {
+        let Some((line, next)) = read_header(&self.buffer, self.pos, "mbulk")? else {
+            return Ok(false);
+        };
+        let len = parse_integer(&line[1..])?;
+        if len > MAX_ARRAY_LEN {
+            return Err("Protocol error: invalid multibulk length".to_string());
+        }
+        self.pos = next;
+        // 与 Redis 一致，*0 和 *-1 当作空命令忽略
+        self.multibulk = Some(Multibulk {
+            remaining: len.max(0) as usize,
+            args: Vec::new(),
+        });
+        Ok(true)
+    }
+
+    // 继续解析数组中的参数，参数只能是批量字符串，全部到齐后返回参数列表
+    fn next_multibulk_command(&mut self) -> Result<Option<Vec<Vec<u8>>>, String> {
+        let Some(multibulk) = self.multibulk.as_mut() else {
+            return Ok(None);
+        };
+        while multibulk.remaining > 0 {
+            let Some((line, next)) = read_header(&self.buffer, self.pos, "bulk")? else {
+                return Ok(None);
+            };
+            if line[0] != b'$' {
+                return Err(format!(
+                    "Protocol error: expected '$', got '{}'",
+                    line[0] as char
+                ));
+            }
+            let len = parse_integer(&line[1..])?;
+            if !(0..=MAX_BULK_LEN).contains(&len) {
+                return Err("Protocol error: invalid bulk length".to_string());
+            }
+            let end = next + len as usize;
+            if self.buffer.len() < end + 2 {
+                return Ok(None);
+            }
+            if &self.buffer[end..end + 2] != b"\r\n" {
+                return Err("Protocol error: bulk string is not terminated by CRLF".to_string());
+            }
+            multibulk.args.push(self.buffer[next..end].to_vec());
+            multibulk.remaining -= 1;
+            self.pos = end + 2;
+        }
+        Ok(self.multibulk.take().map(|multibulk| multibulk.args))
+    }
+
+    fn next_inline_command(&mut self) -> Result<Option<Vec<Vec<u8>>>, String> {
+        let rest = &self.buffer[self.pos..];
+        let Some(idx) = rest.iter().position(|b| *b == b'\n') else {
+            if rest.len() > MAX_INLINE_LEN {
+                return Err("Protocol error: too big inline request".to_string());
+            }
+            return Ok(None);
+        };
+        // 兼容只以 \n 结尾的行
+        let line = rest[..idx].strip_suffix(b"\r").unwrap_or(&rest[..idx]);
+        let args = split_args(line)
+            .ok_or_else(|| "Protocol error: unbalanced quotes in request".to_string())?;
+        self.pos += idx + 1;
+        Ok(Some(args))
+    }
+}
+
+// 从 pos 处解析一个帧，成功时返回帧以及帧结束后的位置，depth 为当前的嵌套层数
+fn parse_frame(buf: &[u8], pos: usize, depth: usize) -> Result<Option<(Frame, usize)>, String> {
+    let Some((line, next)) = read_line(buf, pos) else {
+        return Ok(None);
+    };
+    if line.is_empty() {
+        return Err("Protocol error: empty line".to_string());
+    }
+
+    let payload = &line[1..];
+    match line[0] {
+        b'+' => Ok(Some((
+            Frame::Simple(String::from_utf8_lossy(payload).into_owned()),
+            next,
+        ))),
+        b'-' => Ok(Some((
+            Frame::Error(String::from_utf8_lossy(payload).into_owned()),
+            next,
+        ))),
+        b':' => Ok(Some((Frame::Integer(parse_integer(payload)?), next))),
+        b'$' => {
+            let len = parse_integer(payload)?;
+            if len == -1 {
+                return Ok(Some((Frame::Null, next)));
+            }
+            if !(0..=MAX_BULK_LEN).contains(&len) {
+                return Err("Protocol error: invalid bulk length".to_string());
+            }
+            let end = next + len as usize;
+            // 数据和结尾的 \r\n 都到齐后才算完整
+            if buf.len() < end + 2 {
+                return Ok(None);
+            }
+            if &buf[end..end + 2] != b"\r\n" {
+                return Err("Protocol error: bulk string is not terminated by CRLF".to_string());
+            }
+            Ok(Some((Frame::Bulk(buf[next..end].to_vec()), end + 2)))
+        }
+        b'*' => {
+            let len = parse_integer(payload)?;
+            if len == -1 {
+                return Ok(Some((Frame::NullArray, next)));
+            }
+            if !(0..=MAX_ARRAY_LEN).contains(&len) {
+                return Err("Protocol error: invalid multibulk length".to_string());
+            }
+            if depth >= MAX_NESTING {
+                return Err("Protocol error: too many nested arrays".to_string());
+            }
+            let mut items = Vec::new();
+            let mut cursor = next;
+            for _ in 0..len {
+                match parse_frame(buf, cursor, depth + 1)? {
+                    Some((item, after)) => {
+                        items.push(item);
+                        cursor = after;
+                    }
+                    None => return Ok(None),
+                }
+            }
+            Ok(Some((Frame::Array(items), cursor)))
+        }
+        other => Err(format!(
+            "Protocol error: unexpected type byte '{}'",
+            other as char
+        )),
+    }
+}
+
+// 读取以 \r\n 结尾的一行，返回不含 \r\n 的内容以及下一行的起始位置
+fn read_line(buf: &[u8], pos: usize) -> Option<(&[u8], usize)> {
+    let rest = buf.get(pos..)?;
+    let idx = rest.windows(2).position(|w| w == b"\r\n")?;
+    Some((&rest[..idx], pos + idx + 2))
+}
+
+// 读取数组或批量字符串的头部，头部超过 MAX_INLINE_LEN 仍没有结束时返回错误
+// 只在前 MAX_INLINE_LEN 个字节内查找 \r\n，避免每次读取都扫描整个缓冲区
+fn read_header<'a>(
+    buf: &'a [u8],
+    pos: usize,
+    kind: &str,
+) -> Result<Option<(&'a [u8], usize)>, String> {
+    let limit = buf.len().min(pos + MAX_INLINE_LEN + 2);
+    match read_line(&buf[..limit], pos) {
+        Some(([], _)) => Err("Protocol error: empty line".to_string()),
+        Some(header) => Ok(Some(header)),
+        None if limit - pos > MAX_INLINE_LEN => {
+            Err(format!("Protocol error: too big {} count string", kind))
+        }
+        None => Ok(None),
+    }
+}
+
+fn parse_integer(data: &[u8]) -> Result<i64, String> {
+    std::str::from_utf8(data)
+        .ok()
+        .and_then(|s| s.parse::<i64>().ok())
+        .ok_or_else(|| "Protocol error: invalid integer".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(items: &[&str]) -> Vec<Vec<u8>> {
+        items.iter().map(|item| item.as_bytes().to_vec()).collect()
+    }
+
+    #[test]
+    fn decodes_pipelined_commands() {
+        let mut decoder = Decoder::new();
+        decoder.extend(b"*2\r\n$3\r\nGET\r\n$1\r\nk\r\nPING\r\n*1\r\n$4\r\nPING\r\n");
+        assert_eq!(decoder.next_command(), Ok(Some(args(&["GET", "k"]))));
+        assert_eq!(decoder.next_command(), Ok(Some(args(&["PING"]))));
+        assert_eq!(decoder.next_command(), Ok(Some(args(&["PING"]))));
+        assert_eq!(decoder.next_command(), Ok(None));
+    }
+
+    #[test]
+    fn decodes_command_split_across_reads() {
+        let data = b"*3\r\n$4\r\nSADD\r\n$1\r\ns\r\n$5\r\nhello\r\n";
+        let mut decoder = Decoder::new();
+        for (i, byte) in data.iter().enumerate() {
+            decoder.extend(&[*byte]);
+            let command = decoder.next_command().unwrap();
+            if i + 1 < data.len() {
+                assert_eq!(command, None);
+            } else {
+                assert_eq!(command, Some(args(&["SADD", "s", "hello"])));
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_parsed_args_across_reads() {
+        let mut decoder = Decoder::new();
+        decoder.extend(b"*3\r\n$4\r\nSADD\r\n$1\r\ns\r\n");
+        assert_eq!(decoder.next_command(), Ok(None));
+        // 已解析的参数不再保留在缓冲区中
+        decoder.extend(b"$1\r\n");
+        assert_eq!(decoder.buffer, b"$1\r\n");
+        decoder.extend(b"m\r\n");
+        assert_eq!(decoder.next_command(), Ok(Some(args(&["SADD", "s", "m"]))));
+    }
+
+    #[test]
+    fn ignores_empty_multibulk() {
+        let mut decoder = Decoder::new();
+        decoder.extend(b"*0\r\n*-1\r\n");
+        assert_eq!(decoder.next_command(), Ok(Some(vec![])));
+        assert_eq!(decoder.next_command(), Ok(Some(vec![])));
+        assert_eq!(decoder.next_command(), Ok(None));
+    }
+
+    #[test]
+    fn rejects_nested_aggregates_in_commands() {
+        let mut decoder = Decoder::new();
+        decoder.extend(&b"*1\r\n".repeat(300_000));
+        assert_eq!(
+            decoder.next_command(),
+            Err("Protocol error: expected '$', got '*'".to_string())
+        );
+
+        let mut decoder = Decoder::new();
+        decoder.extend(b"*1\r\n:1\r\n");
+        assert!(decoder.next_command().is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_lengths() {
+        let mut decoder = Decoder::new();
+        decoder.extend(b"*1048577\r\n");
+        assert_eq!(
+            decoder.next_command(),
+            Err("Protocol error: invalid multibulk length".to_string())
+        );
+
+        let mut decoder = Decoder::new();
+        decoder.extend(b"*1\r\n$-1\r\n");
+        assert_eq!(
+            decoder.next_command(),
+            Err("Protocol error: invalid bulk length".to_string())
+        );
+
+        let mut decoder = Decoder::new();
+        decoder.extend(b"*1\r\n$1\r\nab\r\n");
+        assert_eq!(
+            decoder.next_command(),
+            Err("Protocol error: bulk string is not terminated by CRLF".to_string())
+        );
+    }
+
+    #[test]
+    fn huge_declared_length_waits_for_data() {
+        let mut decoder = Decoder::new();
+        decoder.extend(b"*1048576\r\n$1\r\na\r\n");
+        assert_eq!(decoder.next_command(), Ok(None));
+        assert!(decoder.multibulk.as_ref().unwrap().args.capacity() < 1024);
+    }
+
+    #[test]
+    fn caps_header_line() {
+        let mut decoder = Decoder::new();
+        decoder.extend(b"*");
+        decoder.extend(&vec![b'1'; MAX_INLINE_LEN]);
+        assert_eq!(
+            decoder.next_command(),
+            Err("Protocol error: too big mbulk count string".to_string())
+        );
+
+        let mut decoder = Decoder::new();
+        decoder.extend(b"*1\r\n$");
+        decoder.extend(&vec![b'1'; MAX_INLINE_LEN]);
+        assert_eq!(
+            decoder.next_command(),
+            Err("Protocol error: too big bulk count string".to_string())
+        );
+    }
+
+    #[test]
+    fn decodes_inline_commands() {
+        let mut decoder = Decoder::new();
+        decoder.extend(b"SET k \"a b\"\r\nGET k\n");
+        assert_eq!(decoder.next_command(), Ok(Some(args(&["SET", "k", "a b"]))));
+        assert_eq!(decoder.next_command(), Ok(Some(args(&["GET", "k"]))));
+        assert_eq!(decoder.next_command(), Ok(None));
+    }
+
+    #[test]
+    fn decodes_nested_replies() {
+        let mut decoder = Decoder::new();
+        decoder.extend(b"*2\r\n$1\r\n0\r\n*2\r\n:1\r\n$-1\r\n+OK\r\n");
+        assert_eq!(
+            decoder.decode(),
+            Ok(Some(Frame::Array(vec![
+                Frame::Bulk(b"0".to_vec()),
+                Frame::Array(vec![Frame::Integer(1), Frame::Null]),
+            ])))
+        );
+        assert_eq!(decoder.decode(), Ok(Some(Frame::Simple("OK".to_string()))));
+        assert_eq!(decoder.decode(), Ok(None));
+    }
+
+    #[test]
+    fn limits_reply_nesting() {
+        let mut decoder = Decoder::new();
+        decoder.extend(&b"*1\r\n".repeat(300_000));
+        assert_eq!(
+            decoder.decode(),
+            Err("Protocol error: too many nested arrays".to_string())
+        );
+    }
+}
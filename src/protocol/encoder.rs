@@ -0,0 +1,191 @@
+use crate::operations::utils::format_float;
+use crate::protocol::frame::Frame;
+
+// 连接使用的协议版本，默认 RESP2，可通过 HELLO 3 切换到 RESP3
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Protocol {
+    #[default]
+    Resp2,
+    Resp3,
+}
+
+impl Protocol {
+    pub fn version(&self) -> i64 {
+        match self {
+            Protocol::Resp2 => 2,
+            Protocol::Resp3 => 3,
+        }
+    }
+}
+
+// 将帧按连接协商的协议版本编码后追加到 buf 中
+pub fn encode(frame: &Frame, protocol: Protocol, buf: &mut Vec<u8>) {
+    match frame {
+        Frame::Simple(s) => encode_line(b'+', s, buf),
+        Frame::Error(msg) => encode_line(b'-', msg, buf),
+        Frame::Integer(i) => {
+            buf.extend_from_slice(format!(":{}\r\n", i).as_bytes());
+        }
+        Frame::Bulk(data) => encode_bulk(data, buf),
+        Frame::Null => match protocol {
+            Protocol::Resp2 => buf.extend_from_slice(b"$-1\r\n"),
+            Protocol::Resp3 => buf.extend_from_slice(b"_\r\n"),
+        },
+        Frame::NullArray => match protocol {
+            Protocol::Resp2 => buf.extend_from_slice(b"*-1\r\n"),
+            Protocol::Resp3 => buf.extend_from_slice(b"_\r\n"),
+        },
+        Frame::Array(items) => encode_aggregate(b'*', items, protocol, buf),
+        Frame::Double(value) => match protocol {
+            // RESP2 中浮点数以批量字符串返回
+            Protocol::Resp2 => encode_bulk(format_float(*value).as_bytes(), buf),
+            Protocol::Resp3 => {
+                buf.extend_from_slice(format!(",{}\r\n", format_float(*value)).as_bytes());
+            }
+        },
+        Frame::Boolean(value) => match protocol {
+            Protocol::Resp2 => buf.extend_from_slice(if *value { b":1\r\n" } else { b":0\r\n" }),
+            Protocol::Resp3 => buf.extend_from_slice(if *value { b"#t\r\n" } else { b"#f\r\n" }),
+        },
+        Frame::Map(pairs) => {
+            // RESP2 中 map 展开为 key、value 交替的数组
+            let (prefix, len) = match protocol {
+                Protocol::Resp2 => (b'*', pairs.len() * 2),
+                Protocol::Resp3 => (b'%', pairs.len()),
+            };
+            buf.extend_from_slice(format!("{}{}\r\n", prefix as char, len).as_bytes());
+            for (key, value) in pairs {
+                encode(key, protocol, buf);
+                encode(value, protocol, buf);
+            }
+        }
+        Frame::Set(items) => match protocol {
+            Protocol::Resp2 => encode_aggregate(b'*', items, protocol, buf),
+            Protocol::Resp3 => encode_aggregate(b'~', items, protocol, buf),
+        },
+        Frame::Push(items) => match protocol {
+            Protocol::Resp2 => encode_aggregate(b'*', items, protocol, buf),
+            Protocol::Resp3 => encode_aggregate(b'>', items, protocol, buf),
+        },
+    }
+}
+
+// 简单字符串和错误以 \r\n 结尾，内容中的 \r、\n 与 Redis 一样替换为空格，
+// 避免错误信息中回显的用户输入提前结束回复或伪造出额外的回复
+fn encode_line(prefix: u8, text: &str, buf: &mut Vec<u8>) {
+    buf.push(prefix);
+    buf.extend(text.bytes().map(|byte| {
+        if byte == b'\r' || byte == b'\n' {
+            b' '
+        } else {
+            byte
+        }
+    }));
+    buf.extend_from_slice(b"\r\n");
+}
+
+fn encode_bulk(data: &[u8], buf: &mut Vec<u8>) {
+    buf.extend_from_slice(format!("${}\r\n", data.len()).as_bytes());
+    buf.extend_from_slice(data);
+    buf.extend_from_slice(b"\r\n");
+}
+
+fn encode_aggregate(prefix: u8, items: &[Frame], protocol: Protocol, buf: &mut Vec<u8>) {
+    buf.extend_from_slice(format!("{}{}\r\n", prefix as char, items.len()).as_bytes());
+    for item in items {
+        encode(item, protocol, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(frame: &Frame, protocol: Protocol) -> Vec<u8> {
+        let mut buf = Vec::new();
+        encode(frame, protocol, &mut buf);
+        buf
+    }
+
+    #[test]
+    fn line_frames_cannot_contain_crlf() {
+        assert_eq!(
+            encoded(
+                &Frame::error("ERR unknown command 'foo\r\n+OK'"),
+                Protocol::Resp2
+            ),
+            b"-ERR unknown command 'foo  +OK'\r\n"
+        );
+        assert_eq!(
+            encoded(&Frame::Simple("a\nb\rc".to_string()), Protocol::Resp3),
+            b"+a b c\r\n"
+        );
+    }
+
+    #[test]
+    fn null_array_is_distinct_from_null_bulk_in_resp2() {
+        assert_eq!(encoded(&Frame::Null, Protocol::Resp2), b"$-1\r\n");
+        assert_eq!(encoded(&Frame::NullArray, Protocol::Resp2), b"*-1\r\n");
+        assert_eq!(encoded(&Frame::NullArray, Protocol::Resp3), b"_\r\n");
+    }
+
+    #[test]
+    fn resp3_types() {
+        let map = Frame::Map(vec![(
+            Frame::Bulk(b"k".to_vec()),
+            Frame::Set(vec![Frame::Integer(1)]),
+        )]);
+        assert_eq!(
+            encoded(&map, Protocol::Resp3),
+            b"%1\r\n$1\r\nk\r\n~1\r\n:1\r\n"
+        );
+        assert_eq!(encoded(&Frame::Null, Protocol::Resp3), b"_\r\n");
+        assert_eq!(encoded(&Frame::Double(2.5), Protocol::Resp3), b",2.5\r\n");
+        assert_eq!(
+            encoded(&Frame::Double(f64::INFINITY), Protocol::Resp3),
+            b",inf\r\n"
+        );
+        assert_eq!(
+            encoded(&Frame::Double(-1e300), Protocol::Resp3),
+            b",-1e+300\r\n"
+        );
+        assert_eq!(encoded(&Frame::Boolean(true), Protocol::Resp3), b"#t\r\n");
+        assert_eq!(
+            encoded(
+                &Frame::Push(vec![Frame::Simple("message".to_string())]),
+                Protocol::Resp3
+            ),
+            b">1\r\n+message\r\n"
+        );
+    }
+
+    #[test]
+    fn resp3_types_downgrade_on_resp2() {
+        let map = Frame::Map(vec![(
+            Frame::Bulk(b"k".to_vec()),
+            Frame::Set(vec![Frame::Integer(1)]),
+        )]);
+        // map 展开为 key、value 交替的数组，set 变为普通数组
+        assert_eq!(
+            encoded(&map, Protocol::Resp2),
+            b"*2\r\n$1\r\nk\r\n*1\r\n:1\r\n"
+        );
+        assert_eq!(encoded(&Frame::Null, Protocol::Resp2), b"$-1\r\n");
+        assert_eq!(
+            encoded(&Frame::Double(2.5), Protocol::Resp2),
+            b"$3\r\n2.5\r\n"
+        );
+        assert_eq!(
+            encoded(&Frame::Double(f64::NEG_INFINITY), Protocol::Resp2),
+            b"$4\r\n-inf\r\n"
+        );
+        assert_eq!(encoded(&Frame::Boolean(false), Protocol::Resp2), b":0\r\n");
+        assert_eq!(
+            encoded(
+                &Frame::Push(vec![Frame::Simple("message".to_string())]),
+                Protocol::Resp2
+            ),
+            b"*1\r\n+message\r\n"
+        );
+    }
+}
@@ -0,0 +1,65 @@
+// RESP 协议中的一个完整数据帧
+#[derive(Debug, Clone, PartialEq)]
+pub enum Frame {
+    // +OK\r\n
+    Simple(String),
+    // -ERR message\r\n
+    Error(String),
+    // :1000\r\n
+    Integer(i64),
+    // $5\r\nhello\r\n
+    Bulk(Vec<u8>),
+    // $-1\r\n
+    Null,
+    // *-1\r\n，BLPOP 超时、LMPOP 没有元素等本应返回数组的空回复
+    NullArray,
+    // *2\r\n$3\r\nfoo\r\n$3\r\nbar\r\n
+    Array(Vec<Frame>),
+    // 以下为 RESP3 新增的类型，RESP2 连接上会被降级为上面的类型
+    // ,3.14\r\n
+    Double(f64),
+    // #t\r\n
+    Boolean(bool),
+    // %1\r\n+key\r\n+value\r\n
+    Map(Vec<(Frame, Frame)>),
+    // ~2\r\n+a\r\n+b\r\n
+    Set(Vec<Frame>),
+    // >2\r\n+message\r\n+hello\r\n，服务端主动推送的带外消息
+    Push(Vec<Frame>),
+}
+
+impl Frame {
+    // +OK
+    pub fn ok() -> Frame {
+        Frame::Simple("OK".to_string())
+    }
+
+    // 构造错误回复，msg 需要带上 ERR、WRONGTYPE 等前缀
+    pub fn error(msg: impl Into<String>) -> Frame {
+        Frame::Error(msg.into())
+    }
+
+    // 由多个二进制值构成的数组回复
+    pub fn bulks<I>(items: I) -> Frame
+    where
+        I: IntoIterator<Item = Vec<u8>>,
+    {
+        Frame::Array(items.into_iter().map(Frame::Bulk).collect())
+    }
+
+    // 将客户端发来的命令帧转换为参数列表，命令必须是由批量字符串组成的数组
+    pub fn into_args(self) -> Result<Vec<Vec<u8>>, String> {
+        match self {
+            Frame::Array(items) => items
+                .into_iter()
+                .map(|item| match item {
+                    Frame::Bulk(data) => Ok(data),
+                    Frame::Simple(s) => Ok(s.into_bytes()),
+                    Frame::Integer(i) => Ok(i.to_string().into_bytes()),
+                    _ => Err("Protocol error: expected bulk string".to_string()),
+                })
+                .collect(),
+            _ => Err("Protocol error: expected array of bulk strings".to_string()),
+        }
+    }
+}
@@ -0,0 +1,142 @@
+// 内联命令的最大长度，与 Redis 的 PROTO_INLINE_MAX_SIZE 一致
+pub const MAX_INLINE_LEN: usize = 64 * 1024;
+
+// 按 Redis 内联命令的规则拆分一行文本（对应 sdssplitargs）
+// 参数之间以空白分隔，支持双引号（可使用 \n、\xHH 等转义）和单引号（只能转义 \'），
+// 引号不匹配时返回 None
+pub fn split_args(line: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let mut args = Vec::new();
+    let mut i = 0;
+    loop {
+        // 跳过参数之间的空白
+        while i < line.len() && line[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        if i == line.len() {
+            return Some(args);
+        }
+
+        let mut current = Vec::new();
+        let mut in_double = false;
+        let mut in_single = false;
+        loop {
+            if in_double {
+                let c = *line.get(i)?;
+                if c == b'\\'
+                    && i + 3 < line.len()
+                    && line[i + 1] == b'x'
+                    && let Some(byte) = hex_byte(line[i + 2], line[i + 3])
+                {
+                    current.push(byte);
+                    i += 4;
+                    continue;
+                }
+                if c == b'\\' && i + 1 < line.len() {
+                    current.push(match line[i + 1] {
+                        b'n' => b'\n',
+                        b'r' => b'\r',
+                        b't' => b'\t',
+                        b'b' => 0x08,
+                        b'a' => 0x07,
+                        other => other,
+                    });
+                    i += 2;
+                } else if c == b'"' {
+                    // 闭合的引号后面必须是空白或者行尾
+                    if i + 1 < line.len() && !line[i + 1].is_ascii_whitespace() {
+                        return None;
+                    }
+                    i += 1;
+                    break;
+                } else {
+                    current.push(c);
+                    i += 1;
+                }
+            } else if in_single {
+                let c = *line.get(i)?;
+                if c == b'\\' && i + 1 < line.len() && line[i + 1] == b'\'' {
+                    current.push(b'\'');
+                    i += 2;
+                } else if c == b'\'' {
+                    if i + 1 < line.len() && !line[i + 1].is_ascii_whitespace() {
+                        return None;
+                    }
+                    i += 1;
+                    break;
+                } else {
+                    current.push(c);
+                    i += 1;
+                }
+            } else {
+                match line.get(i) {
+                    None => break,
+                    Some(c) if c.is_ascii_whitespace() => break,
+                    Some(b'"') => in_double = true,
+                    Some(b'\'') => in_single = true,
+                    Some(c) => current.push(*c),
+                }
+                i += 1;
+            }
+        }
+        args.push(current);
+    }
+}
+
+fn hex_byte(high: u8, low: u8) -> Option<u8> {
+    let high = (high as char).to_digit(16)?;
+    let low = (low as char).to_digit(16)?;
+    Some((high * 16 + low) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(line: &str) -> Option<Vec<String>> {
+        split_args(line.as_bytes()).map(|args| {
+            args.into_iter()
+                .map(|arg| String::from_utf8_lossy(&arg).into_owned())
+                .collect()
+        })
+    }
+
+    #[test]
+    fn splits_on_whitespace() {
+        assert_eq!(
+            split("set k v"),
+            Some(vec!["set".into(), "k".into(), "v".into()])
+        );
+        assert_eq!(split("  get \t k  "), Some(vec!["get".into(), "k".into()]));
+        assert_eq!(split(""), Some(vec![]));
+        assert_eq!(split("   "), Some(vec![]));
+    }
+
+    #[test]
+    fn double_quotes_support_escapes() {
+        assert_eq!(
+            split(r#"set "a b" "x\ny\t\"z\"""#),
+            Some(vec!["set".into(), "a b".into(), "x\ny\t\"z\"".into()])
+        );
+        assert_eq!(split(r#""\x41\x6a""#), Some(vec!["Aj".into()]));
+        // 不合法的十六进制转义按普通转义处理
+        assert_eq!(split(r#""\xZZ""#), Some(vec!["xZZ".into()]));
+        assert_eq!(
+            split(r#"set k """#),
+            Some(vec!["set".into(), "k".into(), "".into()])
+        );
+    }
+
+    #[test]
+    fn single_quotes_only_escape_quotes() {
+        assert_eq!(split(r"'a\'b'"), Some(vec!["a'b".into()]));
+        assert_eq!(split(r"'a\nb'"), Some(vec![r"a\nb".into()]));
+    }
+
+    #[test]
+    fn rejects_unbalanced_quotes() {
+        assert_eq!(split(r#"set "k v"#), None);
+        assert_eq!(split("set 'k v"), None);
+        assert_eq!(split(r#"set "k"v"#), None);
+        assert_eq!(split("set 'k'v"), None);
+    }
+}
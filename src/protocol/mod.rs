@@ -0,0 +1,4 @@
+pub mod decoder;
+pub mod encoder;
+pub mod frame;
+pub mod inline;
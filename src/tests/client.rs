@@ -1,5 +1,7 @@
+use rudis::protocol::decoder::Decoder;
 use std::io::{self, Write};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
 
 #[tokio::main]
@@ -24,6 +26,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             break;
         }
 
+        // 压测命令：bench <次数> <命令>，例如 bench 10000 SET key value
+        if let Some(rest) = command.strip_prefix("bench ") {
+            match rest.split_once(' ') {
+                Some((count, bench_command)) if count.parse::<usize>().is_ok() => {
+                    bench(&mut stream, count.parse().unwrap(), bench_command).await?;
+                }
+                _ => println!("用法: bench <次数> <命令>"),
+            }
+            continue;
+        }
+
         // 转换命令为 RESP 格式
         let resp_command = to_resp_format(&command);
 
@@ -48,7 +61,77 @@ fn to_resp_format(command: &str) -> String {
     let mut resp = format!("*{}\r\n", parts.len()); // 数组大小
 
     for part in parts {
-        resp.push_str(&format!("${}\r\n{}\r\n", part.as_bytes().len(), part));
+        resp.push_str(&format!("${}\r\n{}\r\n", part.len(), part));
     }
     resp
-}
\ No newline at end of file
+}
+
+// 分别以逐条请求和流水线两种方式执行同一条命令 count 次，对比吞吐量
+async fn bench(stream: &mut TcpStream, count: usize, command: &str) -> io::Result<()> {
+    let resp_command = to_resp_format(command);
+    let mut decoder = Decoder::new();
+
+    // 逐条发送，每条命令都等待回复后再发送下一条
+    let start = Instant::now();
+    for _ in 0..count {
+        stream.write_all(resp_command.as_bytes()).await?;
+        read_replies(stream, &mut decoder, 1).await?;
+    }
+    let sequential = start.elapsed();
+
+    // 流水线，一次写入所有命令，同时读取回复，避免双方的发送缓冲区互相等待
+    let batch = resp_command.repeat(count);
+    let (mut reader, mut writer) = stream.split();
+    let start = Instant::now();
+    let (written, read) = tokio::join!(
+        writer.write_all(batch.as_bytes()),
+        read_replies(&mut reader, &mut decoder, count)
+    );
+    written?;
+    read?;
+    let pipelined = start.elapsed();
+
+    println!("逐条请求: {}", format_throughput(count, sequential));
+    println!("流水线:   {}", format_throughput(count, pipelined));
+    println!(
+        "提升:     {:.1} 倍",
+        sequential.as_secs_f64() / pipelined.as_secs_f64().max(f64::EPSILON)
+    );
+    Ok(())
+}
+
+// 读取 count 个完整的回复
+async fn read_replies<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    decoder: &mut Decoder,
+    count: usize,
+) -> io::Result<()> {
+    let mut received = 0;
+    let mut buffer = [0; 16 * 1024];
+    loop {
+        while received < count {
+            match decoder.decode() {
+                Ok(Some(_)) => received += 1,
+                Ok(None) => break,
+                Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+            }
+        }
+        if received == count {
+            return Ok(());
+        }
+        let n = reader.read(&mut buffer).await?;
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        decoder.extend(&buffer[..n]);
+    }
+}
+
+fn format_throughput(count: usize, elapsed: Duration) -> String {
+    format!(
+        "{} 条命令耗时 {:.3} 秒，{:.0} 条/秒",
+        count,
+        elapsed.as_secs_f64(),
+        count as f64 / elapsed.as_secs_f64().max(f64::EPSILON)
+    )
+}